@@ -0,0 +1,13 @@
+/* user 表的结构和初始数据 */
+create table user (
+    id int not null,
+    col2 int,
+    col3 char(5),
+    col4 varchar(11),
+    name varchar(15) not null,
+    primary key (id)
+);
+
+insert into user (id, col2, col3, col4, name) values (1, 4, 'aaaaa', 'bbbb', 'cc');
+insert into user (id, name) values (2, 'Bob Li');
+insert into user values (3, 10, 'x', null, "O'Neil");
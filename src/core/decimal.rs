@@ -0,0 +1,226 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use serde_derive::{Deserialize, Serialize};
+
+// 定点数，值为 value / 10^scale，最多 38 位有效数字
+// 舍入都是四舍五入（远离 0 的方向），加法和除法在溢出时返回 None
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Decimal {
+    value: i128,
+    scale: u32,
+}
+
+pub const MAX_PRECISION: usize = 38;
+
+// avg 的结果比参数多保留的小数位数
+const AVG_SCALE: u32 = 4;
+
+impl Decimal {
+    pub fn new(value: i128, scale: u32) -> Self {
+        Decimal { value, scale }
+    }
+
+    pub fn value(&self) -> i128 {
+        self.value
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    // [+-]digits[.digits]
+    pub fn parse(s: &str) -> Option<Decimal> {
+        let s = s.trim();
+        let (negative, digits) = match s.as_bytes().first()? {
+            b'-' => (true, &s[1..]),
+            b'+' => (false, &s[1..]),
+            _ => (false, s),
+        };
+        let (int, fraction) = match digits.find('.') {
+            Some(i) => (&digits[..i], &digits[i + 1..]),
+            None => (digits, ""),
+        };
+        if int.is_empty() && fraction.is_empty()
+            || !int
+                .bytes()
+                .chain(fraction.bytes())
+                .all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+        let mut value: i128 = 0;
+        for c in int.bytes().chain(fraction.bytes()) {
+            value = value.checked_mul(10)?.checked_add((c - b'0') as i128)?;
+        }
+        let decimal = Decimal::new(if negative { -value } else { value }, fraction.len() as u32);
+        if decimal.precision() > MAX_PRECISION {
+            return None;
+        }
+        Some(decimal)
+    }
+
+    // 浮点数按最短的十进制表示转换，不超过 15 位有效数字时和字面量的写法一致
+    pub fn from_f64(n: f64) -> Option<Decimal> {
+        if !n.is_finite() {
+            return None;
+        }
+        Decimal::parse(&n.to_string())
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.value as f64 / 10f64.powi(self.scale as i32)
+    }
+
+    // 有效数字的位数，整数部分为 0 时不计算
+    pub fn precision(&self) -> usize {
+        let digits = self.value.unsigned_abs().to_string().len();
+        digits.max(self.scale as usize)
+    }
+
+    // 调整小数位数，减少时四舍五入
+    pub fn rescale(&self, scale: u32) -> Option<Decimal> {
+        if scale >= self.scale {
+            let factor = 10i128.checked_pow(scale - self.scale)?;
+            return Some(Decimal::new(self.value.checked_mul(factor)?, scale));
+        }
+        let factor = 10i128.checked_pow(self.scale - scale)?;
+        Some(Decimal::new(divide(self.value, factor), scale))
+    }
+
+    pub fn checked_add(&self, other: &Decimal) -> Option<Decimal> {
+        let scale = self.scale.max(other.scale);
+        let (a, b) = (self.rescale(scale)?, other.rescale(scale)?);
+        Some(Decimal::new(a.value.checked_add(b.value)?, scale))
+    }
+
+    // 平均值，比和多保留 4 位小数
+    pub fn average(&self, count: usize) -> Option<Decimal> {
+        let scale = (self.scale + AVG_SCALE).min(MAX_PRECISION as u32);
+        let sum = self.rescale(scale)?;
+        Some(Decimal::new(divide(sum.value, count as i128), scale))
+    }
+
+    // 去掉小数末尾的 0，1.50 和 1.5 得到相同的结果
+    pub fn normalize(&self) -> Decimal {
+        let mut decimal = *self;
+        while decimal.scale > 0 && decimal.value % 10 == 0 {
+            decimal.value /= 10;
+            decimal.scale -= 1;
+        }
+        decimal
+    }
+}
+
+// 整数除法，余数四舍五入
+fn divide(value: i128, divisor: i128) -> i128 {
+    let quotient = value / divisor;
+    let remainder = value % divisor;
+    if remainder.unsigned_abs() * 2 >= divisor.unsigned_abs() {
+        quotient + value.signum() * divisor.signum()
+    } else {
+        quotient
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(n: i64) -> Self {
+        Decimal::new(n as i128, 0)
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        match (self.rescale(scale), other.rescale(scale)) {
+            (Some(a), Some(b)) => a.value.cmp(&b.value),
+            // 对齐小数位数时溢出，说明溢出的一方绝对值更大
+            (None, _) => 0.cmp(&self.value).reverse(),
+            (_, None) => 0.cmp(&other.value),
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = format!(
+            "{:0>width$}",
+            self.value.unsigned_abs(),
+            width = self.scale as usize + 1
+        );
+        let (int, fraction) = digits.split_at(digits.len() - self.scale as usize);
+        if self.value < 0 {
+            write!(f, "-")?;
+        }
+        if fraction.is_empty() {
+            write!(f, "{}", int)
+        } else {
+            write!(f, "{}.{}", int, fraction)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn d(s: &str) -> Decimal {
+        Decimal::parse(s).unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(d("12.30"), Decimal::new(1230, 2));
+        assert_eq!(d("-0.05"), Decimal::new(-5, 2));
+        assert_eq!(d(".5"), Decimal::new(5, 1));
+        assert_eq!(d("7."), Decimal::new(7, 0));
+        for s in [
+            "12.30",
+            "-0.05",
+            "0.001",
+            "100",
+            "-123456789012345678901234567.89",
+        ] {
+            assert_eq!(d(s).to_string(), s);
+        }
+        for s in ["", ".", "-", "1.2.3", "1e3", "abc", "1 2"] {
+            assert_eq!(Decimal::parse(s), None, "{}", s);
+        }
+        assert_eq!(Decimal::parse(&"9".repeat(39)), None);
+        assert_eq!(Decimal::from_f64(0.1), Some(d("0.1")));
+        assert_eq!(Decimal::from_f64(f64::NAN), None);
+    }
+
+    #[test]
+    fn rounding() {
+        assert_eq!(d("2.345").rescale(2), Some(d("2.35")));
+        assert_eq!(d("-2.345").rescale(2), Some(d("-2.35")));
+        assert_eq!(d("2.344").rescale(2), Some(d("2.34")));
+        assert_eq!(d("0.5").rescale(0), Some(d("1")));
+        assert_eq!(d("1.5").rescale(3), Some(d("1.500")));
+        assert_eq!(d("12.50").precision(), 4);
+        assert_eq!(d("0.001").precision(), 3);
+        assert_eq!(d("1.50").normalize(), d("1.5"));
+    }
+
+    #[test]
+    fn arithmetic() {
+        // 0.1 + 0.2 精确等于 0.3
+        assert_eq!(d("0.1").checked_add(&d("0.2")), Some(d("0.3")));
+        assert_eq!(d("1.25").checked_add(&d("-3")), Some(d("-1.75")));
+        assert_eq!(d("10.00").average(3), Some(d("3.333333")));
+        assert_eq!(d("-2.00").average(3), Some(d("-0.666667")));
+        assert_eq!(d("1.50").cmp(&d("1.5")), Ordering::Equal);
+        assert_eq!(d("-1").cmp(&d("0.001")), Ordering::Less);
+        assert_eq!(
+            Decimal::new(i128::MAX / 10, 0).cmp(&Decimal::new(1, 37)),
+            Ordering::Greater
+        );
+    }
+}
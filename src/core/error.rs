@@ -0,0 +1,144 @@
+use std::fmt;
+
+use super::value::Value;
+use crate::parser::error::ParseError;
+
+#[derive(Debug, PartialEq)]
+pub enum DbError {
+    Parse(ParseError),
+    Io(String),
+    NoSuchTable(String),
+    TableExists(String),
+    NoSuchDatabase(String),
+    DatabaseExists(String),
+    // 数据库名会作为目录名，不能包含路径分隔符
+    InvalidName(String),
+    NoSuchColumn(String),
+    ColumnExists(String),
+    DropPrimaryKey(String),
+    DuplicateKey {
+        column: String,
+        value: Value,
+    },
+    IndexExists(String),
+    NoSuchIndex(String),
+    // text 和 blob 列不能建索引
+    InvalidIndexColumn(String),
+    // 唯一索引中已经有相同的值
+    DuplicateEntry {
+        index: String,
+        values: Vec<Value>,
+    },
+    // 表只剩一列时不能再删除
+    LastColumn(String),
+    AmbiguousColumn(String),
+    // 插入的值和字段类型不匹配
+    TypeMismatch {
+        column: String,
+        fieldtype: String,
+        value: Value,
+    },
+    NotNull(String),
+    TooLong {
+        column: String,
+        max: usize,
+    },
+    OutOfRange {
+        column: String,
+        value: Value,
+    },
+    // 表达式中的类型错误，比如字符串和布尔值比较
+    Type(String),
+    RowTooLarge(usize),
+    // 插入的值的个数和字段个数不一致
+    ValueCount {
+        expected: usize,
+        found: usize,
+    },
+    // 预处理语句的参数个数不对
+    ParameterCount {
+        expected: usize,
+        found: usize,
+    },
+    UnboundParameter(usize),
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DbError::Parse(err) => write!(f, "{}", err),
+            DbError::Io(message) => write!(f, "{}", message),
+            DbError::NoSuchTable(table) => write!(f, "table `{}` does not exist", table),
+            DbError::TableExists(table) => write!(f, "table `{}` already exists", table),
+            DbError::NoSuchDatabase(name) => write!(f, "database `{}` does not exist", name),
+            DbError::DatabaseExists(name) => write!(f, "database `{}` already exists", name),
+            DbError::InvalidName(name) => write!(f, "`{}` is not a valid name", name),
+            DbError::NoSuchColumn(column) => write!(f, "unknown column `{}`", column),
+            DbError::ColumnExists(column) => write!(f, "column `{}` already exists", column),
+            DbError::DropPrimaryKey(column) => {
+                write!(f, "cannot drop primary key column `{}`", column)
+            }
+            DbError::DuplicateKey { column, value } => {
+                write!(f, "duplicate value {} for primary key `{}`", value, column)
+            }
+            DbError::IndexExists(index) => write!(f, "index `{}` already exists", index),
+            DbError::NoSuchIndex(index) => write!(f, "index `{}` does not exist", index),
+            DbError::InvalidIndexColumn(column) => {
+                write!(
+                    f,
+                    "cannot create an index on text or blob column `{}`",
+                    column
+                )
+            }
+            DbError::DuplicateEntry { index, values } => {
+                let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+                write!(
+                    f,
+                    "duplicate entry ({}) for unique index `{}`",
+                    values.join(", "),
+                    index
+                )
+            }
+            DbError::LastColumn(column) => {
+                write!(f, "cannot drop `{}`, the only column of the table", column)
+            }
+            DbError::AmbiguousColumn(column) => write!(f, "column `{}` is ambiguous", column),
+            DbError::TypeMismatch {
+                column,
+                fieldtype,
+                value,
+            } => write!(
+                f,
+                "column `{}` is {}, got {} value {}",
+                column,
+                fieldtype,
+                value.type_name(),
+                value
+            ),
+            DbError::NotNull(column) => write!(f, "column `{}` cannot be null", column),
+            DbError::TooLong { column, max } => {
+                write!(f, "value for column `{}` is longer than {}", column, max)
+            }
+            DbError::OutOfRange { column, value } => {
+                write!(f, "value {} is out of range for column `{}`", value, column)
+            }
+            DbError::Type(message) => write!(f, "{}", message),
+            DbError::RowTooLarge(len) => write!(f, "row of {} bytes does not fit in a page", len),
+            DbError::ValueCount { expected, found } => {
+                write!(f, "expected {} values, found {}", expected, found)
+            }
+            DbError::ParameterCount { expected, found } => {
+                write!(f, "expected {} parameters, found {}", expected, found)
+            }
+            DbError::UnboundParameter(n) => write!(f, "parameter ${} is not bound", n),
+        }
+    }
+}
+
+impl std::error::Error for DbError {}
+
+impl From<ParseError> for DbError {
+    fn from(err: ParseError) -> Self {
+        DbError::Parse(err)
+    }
+}
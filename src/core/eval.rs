@@ -0,0 +1,581 @@
+use std::cmp::Ordering;
+
+use super::decimal::Decimal;
+use super::error::DbError;
+use super::executor::ResultSet;
+use super::value::Value;
+use crate::parser::ast::{AggregateFunc, BinaryOp, Expr, Select, UnaryOp};
+
+// 结果中的一列，table 是表名或者别名
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub table: String,
+    pub name: String,
+}
+
+// 执行表达式中的子查询，outer 是外层查询当前的行
+pub trait QueryRunner {
+    fn run(&self, select: &Select, outer: &Scope) -> Result<ResultSet, DbError>;
+}
+
+// 求值的上下文：外层查询的行，以及执行子查询的方式
+#[derive(Clone, Copy, Default)]
+pub struct Context<'a> {
+    pub outer: Option<&'a Scope<'a>>,
+    pub runner: Option<&'a dyn QueryRunner>,
+}
+
+// 表达式求值时能访问到的一行数据
+pub struct Scope<'a> {
+    pub columns: &'a [Column],
+    pub values: &'a [Value],
+    context: Context<'a>,
+}
+
+impl<'a> Scope<'a> {
+    pub fn new(columns: &'a [Column], values: &'a [Value]) -> Self {
+        Scope::with(Context::default(), columns, values)
+    }
+
+    pub fn with(context: Context<'a>, columns: &'a [Column], values: &'a [Value]) -> Self {
+        Scope {
+            columns,
+            values,
+            context,
+        }
+    }
+
+    pub fn context(&self) -> Context<'a> {
+        self.context
+    }
+
+    // 子查询的上下文，子查询中找不到的列到这一行中找
+    pub fn nested(&self) -> Context<'_> {
+        Context {
+            outer: Some(self),
+            runner: self.context.runner,
+        }
+    }
+
+    fn run(&self, select: &Select) -> Result<ResultSet, DbError> {
+        match self.context.runner {
+            Some(runner) => runner.run(select, self),
+            None => Err(DbError::Type("subquery is not allowed here".to_string())),
+        }
+    }
+
+    // 没有限定表名时按顺序找第一个同名的列，多张表中都有这一列时报错
+    fn lookup(&self, table: &Option<String>, name: &str) -> Result<Value, DbError> {
+        let mut found: Option<usize> = None;
+        for (i, column) in self.columns.iter().enumerate() {
+            if column.name != name || table.as_ref().is_some_and(|t| t != &column.table) {
+                continue;
+            }
+            match found {
+                None => found = Some(i),
+                Some(j) => {
+                    let other = &self.columns[j].table;
+                    if !other.is_empty() && !column.table.is_empty() && other != &column.table {
+                        return Err(DbError::AmbiguousColumn(name.to_string()));
+                    }
+                }
+            }
+        }
+        match (found, self.context.outer) {
+            (Some(i), _) => Ok(self.values[i].clone()),
+            (None, Some(outer)) => outer.lookup(table, name),
+            (None, None) => Err(DbError::NoSuchColumn(match table {
+                Some(table) => format!("{}.{}", table, name),
+                None => name.to_string(),
+            })),
+        }
+    }
+}
+
+// 表达式中的列是否都在 columns 中
+pub fn covers(columns: &[Column], expr: &Expr) -> bool {
+    match expr {
+        Expr::Column { table, name } => columns.iter().any(|column| {
+            &column.name == name && table.as_ref().is_none_or(|t| t == &column.table)
+        }),
+        Expr::Aggregate { .. } | Expr::Subquery(_) | Expr::Exists(_) | Expr::InSubquery { .. } => {
+            false
+        }
+        expr => expr.children().iter().all(|child| covers(columns, child)),
+    }
+}
+
+pub fn eval(expr: &Expr, scope: &Scope) -> Result<Value, DbError> {
+    match expr {
+        Expr::Literal(value) => Ok(value.clone()),
+        // 参数在执行之前就已经替换为字面量
+        Expr::Placeholder(i) => Err(DbError::UnboundParameter(i + 1)),
+        Expr::Column { table, name } => scope.lookup(table, name),
+        Expr::Unary {
+            op: UnaryOp::Not,
+            expr,
+        } => Ok(match eval(expr, scope)?.to_bool() {
+            Some(b) => Value::Bool(!b),
+            None => Value::Null,
+        }),
+        Expr::Binary { left, op, right } => {
+            let left = eval(left, scope)?;
+            match op {
+                // 三值逻辑：false and NULL 为 false，true or NULL 为 true
+                BinaryOp::And => {
+                    if left.to_bool() == Some(false) {
+                        return Ok(Value::Bool(false));
+                    }
+                    Ok(match (left.to_bool(), eval(right, scope)?.to_bool()) {
+                        (_, Some(false)) => Value::Bool(false),
+                        (Some(true), Some(true)) => Value::Bool(true),
+                        _ => Value::Null,
+                    })
+                }
+                BinaryOp::Or => {
+                    if left.to_bool() == Some(true) {
+                        return Ok(Value::Bool(true));
+                    }
+                    Ok(match (left.to_bool(), eval(right, scope)?.to_bool()) {
+                        (_, Some(true)) => Value::Bool(true),
+                        (Some(false), Some(false)) => Value::Bool(false),
+                        _ => Value::Null,
+                    })
+                }
+                op => compare(&left, *op, &eval(right, scope)?),
+            }
+        }
+        // 聚合函数在分组时已经被替换为结果，这里出现说明用在了 where 之类的地方
+        Expr::Aggregate { func, .. } => Err(DbError::Type(format!(
+            "aggregate function {} is not allowed here",
+            func.name()
+        ))),
+        Expr::Exists(select) => Ok(Value::Bool(!scope.run(select)?.rows.is_empty())),
+        Expr::Subquery(select) => {
+            let res = single_column(scope.run(select)?)?;
+            match res.len() {
+                0 => Ok(Value::Null),
+                1 => Ok(res.into_iter().next().unwrap()),
+                _ => Err(DbError::Type(
+                    "subquery returns more than one row".to_string(),
+                )),
+            }
+        }
+        // 有相等的值时为 true；没有相等的值但有 NULL 时为 NULL
+        Expr::InSubquery {
+            expr,
+            subquery,
+            negated,
+        } => {
+            let value = eval(expr, scope)?;
+            let values = single_column(scope.run(subquery)?)?;
+            Ok(negate(contains(&value, &values)?, *negated))
+        }
+        Expr::InList {
+            expr,
+            list,
+            negated,
+        } => {
+            let value = eval(expr, scope)?;
+            let values = list
+                .iter()
+                .map(|e| eval(e, scope))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(negate(contains(&value, &values)?, *negated))
+        }
+        Expr::Between {
+            expr,
+            low,
+            high,
+            negated,
+        } => {
+            let value = eval(expr, scope)?;
+            let low = compare(&value, BinaryOp::GtEq, &eval(low, scope)?)?;
+            let high = compare(&value, BinaryOp::LtEq, &eval(high, scope)?)?;
+            let res = match (low.to_bool(), high.to_bool()) {
+                (Some(false), _) | (_, Some(false)) => Value::Bool(false),
+                (Some(true), Some(true)) => Value::Bool(true),
+                _ => Value::Null,
+            };
+            Ok(negate(res, *negated))
+        }
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            negated,
+        } => {
+            let value = eval(expr, scope)?;
+            let pattern = eval(pattern, scope)?;
+            let escape = match escape {
+                Some(escape) => match eval(escape, scope)? {
+                    Value::Str(s) if s.chars().count() == 1 => s.chars().next(),
+                    value => {
+                        return Err(DbError::Type(format!(
+                            "escape must be a single character, got {}",
+                            value
+                        )))
+                    }
+                },
+                None => None,
+            };
+            if value.is_null() || pattern.is_null() {
+                return Ok(Value::Null);
+            }
+            let text: Vec<char> = like_text(&value)?.chars().collect();
+            let pattern = like_pattern(&like_text(&pattern)?, escape)?;
+            Ok(negate(Value::Bool(like(&text, &pattern)), *negated))
+        }
+    }
+}
+
+// not 的三值逻辑版本，negated 为 false 时原样返回
+fn negate(value: Value, negated: bool) -> Value {
+    match (value, negated) {
+        (Value::Bool(b), true) => Value::Bool(!b),
+        (value, _) => value,
+    }
+}
+
+// in 的结果：有相等的值时为 true；没有相等的值但有 NULL 时为 NULL
+fn contains(value: &Value, values: &[Value]) -> Result<Value, DbError> {
+    let mut res = Value::Bool(false);
+    for other in values.iter() {
+        match compare(value, BinaryOp::Eq, other)? {
+            Value::Bool(true) => return Ok(Value::Bool(true)),
+            Value::Null => res = Value::Null,
+            _ => {}
+        }
+    }
+    Ok(res)
+}
+
+// like 两边的值，数字、日期和时间按文本匹配，blob 按 UTF-8 解码
+fn like_text(value: &Value) -> Result<String, DbError> {
+    match value {
+        Value::Str(s) => Ok(s.clone()),
+        Value::Bytes(b) => Ok(String::from_utf8_lossy(b).to_string()),
+        Value::Int(_)
+        | Value::Float(_)
+        | Value::Decimal(_)
+        | Value::Date(_)
+        | Value::Time(_)
+        | Value::DateTime(_) => Ok(value.to_string()),
+        value => Err(DbError::Type(format!(
+            "cannot match {} with like",
+            value.type_name()
+        ))),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum LikeToken {
+    Any, // %
+    One, // _
+    Char(char),
+}
+
+fn like_pattern(pattern: &str, escape: Option<char>) -> Result<Vec<LikeToken>, DbError> {
+    let mut tokens = vec![];
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        tokens.push(match c {
+            c if Some(c) == escape => match chars.next() {
+                Some(c) => LikeToken::Char(c),
+                None => {
+                    return Err(DbError::Type(format!(
+                        "like pattern `{}` ends with the escape character",
+                        pattern
+                    )))
+                }
+            },
+            '%' => LikeToken::Any,
+            '_' => LikeToken::One,
+            c => LikeToken::Char(c),
+        });
+    }
+    Ok(tokens)
+}
+
+// 通配符匹配，遇到不匹配时回到上一个 % 多吞一个字符重试
+fn like(text: &[char], pattern: &[LikeToken]) -> bool {
+    let (mut t, mut p) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(LikeToken::Any) => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(LikeToken::One) => {
+                t += 1;
+                p += 1;
+            }
+            Some(LikeToken::Char(c)) if *c == text[t] => {
+                t += 1;
+                p += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    star = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|token| *token == LikeToken::Any)
+}
+
+// 用在表达式里的子查询只能返回一列
+fn single_column(res: ResultSet) -> Result<Vec<Value>, DbError> {
+    if res.columns.len() != 1 {
+        return Err(DbError::Type(format!(
+            "subquery returns {} columns, expected 1",
+            res.columns.len()
+        )));
+    }
+    Ok(res.rows.into_iter().map(|mut row| row.remove(0)).collect())
+}
+
+// 把表达式中的聚合函数替换为在这一组记录上计算出的值
+pub fn resolve_aggregates(
+    context: Context,
+    expr: &Expr,
+    columns: &[Column],
+    rows: &[Vec<Value>],
+) -> Result<Expr, DbError> {
+    match expr {
+        Expr::Aggregate { func, arg } => Ok(Expr::Literal(aggregate(
+            context,
+            *func,
+            arg.as_deref(),
+            columns,
+            rows,
+        )?)),
+        expr => expr.map_children(|child| resolve_aggregates(context, child, columns, rows)),
+    }
+}
+
+// NULL 不参与计算；除了 count 之外，没有可计算的值时结果为 NULL
+fn aggregate(
+    context: Context,
+    func: AggregateFunc,
+    arg: Option<&Expr>,
+    columns: &[Column],
+    rows: &[Vec<Value>],
+) -> Result<Value, DbError> {
+    let arg = match arg {
+        Some(arg) => arg,
+        None => return Ok(Value::Int(rows.len() as i64)),
+    };
+    if arg.has_aggregate() {
+        return Err(DbError::Type(format!(
+            "aggregate function {} cannot contain another aggregate",
+            func.name()
+        )));
+    }
+    let mut values = vec![];
+    for row in rows.iter() {
+        let value = eval(arg, &Scope::with(context, columns, row))?;
+        if !value.is_null() {
+            values.push(value);
+        }
+    }
+    match func {
+        AggregateFunc::Count => Ok(Value::Int(values.len() as i64)),
+        AggregateFunc::Sum | AggregateFunc::Avg => {
+            if values.is_empty() {
+                return Ok(Value::Null);
+            }
+            // 都是整数时结果也是整数；有 decimal 没有浮点数时按 decimal 精确计算，
+            // 平均值多保留 4 位小数；溢出或者有浮点数时按浮点数计算
+            let mut int_sum = Some(0i64);
+            let mut decimal_sum = Some(Decimal::from(0));
+            let mut has_decimal = false;
+            let mut float_sum = 0.0;
+            for value in values.iter() {
+                match value {
+                    Value::Int(n) => {
+                        int_sum = int_sum.and_then(|sum| sum.checked_add(*n));
+                        decimal_sum = decimal_sum.and_then(|sum| sum.checked_add(&(*n).into()));
+                        float_sum += *n as f64;
+                    }
+                    Value::Decimal(d) => {
+                        int_sum = None;
+                        has_decimal = true;
+                        decimal_sum = decimal_sum.and_then(|sum| sum.checked_add(d));
+                        float_sum += d.to_f64();
+                    }
+                    Value::Float(n) => {
+                        int_sum = None;
+                        decimal_sum = None;
+                        float_sum += n;
+                    }
+                    value => {
+                        return Err(DbError::Type(format!(
+                            "cannot compute {} of {} values",
+                            func.name(),
+                            value.type_name()
+                        )))
+                    }
+                }
+            }
+            let decimal_result = match decimal_sum {
+                Some(sum) if has_decimal && func == AggregateFunc::Sum => Some(sum),
+                Some(sum) if has_decimal => sum.average(values.len()),
+                _ => None,
+            };
+            if let Some(result) = decimal_result {
+                return Ok(Value::Decimal(result));
+            }
+            Ok(match (func, int_sum) {
+                (AggregateFunc::Sum, Some(sum)) => Value::Int(sum),
+                (AggregateFunc::Sum, None) => Value::Float(float_sum),
+                _ => Value::Float(float_sum / values.len() as f64),
+            })
+        }
+        AggregateFunc::Min | AggregateFunc::Max => {
+            let mut best: Option<Value> = None;
+            for value in values {
+                let replace = match &best {
+                    None => true,
+                    Some(current) => match value.compare(current) {
+                        Some(ordering) => {
+                            (func == AggregateFunc::Min && ordering == Ordering::Less)
+                                || (func == AggregateFunc::Max && ordering == Ordering::Greater)
+                        }
+                        None => {
+                            return Err(DbError::Type(format!(
+                                "cannot compare {} with {}",
+                                value.type_name(),
+                                current.type_name()
+                            )))
+                        }
+                    },
+                };
+                if replace {
+                    best = Some(value);
+                }
+            }
+            Ok(best.unwrap_or(Value::Null))
+        }
+    }
+}
+
+fn compare(left: &Value, op: BinaryOp, right: &Value) -> Result<Value, DbError> {
+    if left.is_null() || right.is_null() {
+        return Ok(Value::Null);
+    }
+    let ordering = match left.compare(right) {
+        Some(ordering) => ordering,
+        None => {
+            return Err(DbError::Type(format!(
+                "cannot compare {} with {}",
+                left.type_name(),
+                right.type_name()
+            )))
+        }
+    };
+    let b = match op {
+        BinaryOp::Eq => ordering == Ordering::Equal,
+        BinaryOp::NotEq => ordering != Ordering::Equal,
+        BinaryOp::Lt => ordering == Ordering::Less,
+        BinaryOp::LtEq => ordering != Ordering::Greater,
+        BinaryOp::Gt => ordering == Ordering::Greater,
+        BinaryOp::GtEq => ordering != Ordering::Less,
+        BinaryOp::And | BinaryOp::Or => unreachable!(),
+    };
+    Ok(Value::Bool(b))
+}
+
+// where 条件是否成立，NULL 视为不成立
+pub fn is_true(expr: &Expr, scope: &Scope) -> Result<bool, DbError> {
+    Ok(eval(expr, scope)?.to_bool() == Some(true))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::ast::Statement::Select;
+    use crate::parser::parser::Parser;
+    use crate::parser::token;
+
+    fn where_of(sql: &str) -> Expr {
+        match Parser::new(token::tokenize(sql)).parse().unwrap() {
+            Select(select) => select.selection.unwrap(),
+            statement => panic!("not a select statement: {:?}", statement),
+        }
+    }
+
+    #[test]
+    fn three_valued_logic() {
+        let columns = vec![
+            Column {
+                table: "t".to_string(),
+                name: "a".to_string(),
+            },
+            Column {
+                table: "t".to_string(),
+                name: "b".to_string(),
+            },
+        ];
+        let values = vec![Value::Int(1), Value::Null];
+        let scope = Scope::new(&columns, &values);
+        let check = |sql: &str| eval(&where_of(sql), &scope).unwrap();
+        assert_eq!(
+            check("select * from t where a = 1 or b = 2"),
+            Value::Bool(true)
+        );
+        assert_eq!(check("select * from t where a = 2 or b = 2"), Value::Null);
+        assert_eq!(
+            check("select * from t where a = 2 and b = 2"),
+            Value::Bool(false)
+        );
+        assert_eq!(check("select * from t where not b = 2"), Value::Null);
+        assert_eq!(
+            check("select * from t where not (a > 1 or a <= 0)"),
+            Value::Bool(true)
+        );
+        assert_eq!(check("select * from t where a <> 1"), Value::Bool(false));
+        assert_eq!(
+            eval(&where_of("select * from t where c = 1"), &scope),
+            Err(DbError::NoSuchColumn("c".to_string()))
+        );
+        assert_eq!(check("select * from t where a in (2, b)"), Value::Null);
+        assert_eq!(
+            check("select * from t where a not in (2, 1, b)"),
+            Value::Bool(false)
+        );
+        assert_eq!(
+            check("select * from t where a between 0 and b"),
+            Value::Null
+        );
+        assert_eq!(
+            check("select * from t where a not between 2 and b"),
+            Value::Bool(true)
+        );
+        assert_eq!(check("select * from t where b like '%'"), Value::Null);
+    }
+
+    #[test]
+    fn like_match() {
+        let matches = |text: &str, pattern: &str, escape: Option<char>| {
+            let text: Vec<char> = text.chars().collect();
+            like(&text, &like_pattern(pattern, escape).unwrap())
+        };
+        assert!(matches("abxyc", "ab%_c", None));
+        assert!(matches("abxc", "ab%_c", None));
+        assert!(!matches("abc", "ab%_c", None));
+        assert!(matches("", "%", None));
+        assert!(!matches("", "_", None));
+        assert!(matches("中文字段", "中%段", None));
+        assert!(matches("a%b", "a!%b", Some('!')));
+        assert!(!matches("axb", "a!%b", Some('!')));
+        assert!(matches("a_b!", "a#_b!", Some('#')));
+        assert!(matches("mississippi", "%iss%ppi", None));
+        assert!(!matches("Abc", "abc", None));
+        assert!(like_pattern("ab!", Some('!')).is_err());
+    }
+}
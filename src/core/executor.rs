@@ -0,0 +1,2955 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+use super::error::DbError;
+use super::eval::{self, Column, Context, QueryRunner, Scope};
+use super::index::{self, Index};
+use super::page::{PageManager, MAX_ROW_SIZE};
+use super::row::RowManager;
+use super::schema::{IndexSchema, Schema};
+use super::value::Value;
+use crate::parser::ast::{
+    AlterOperation, AlterTable, BinaryOp, CreateIndex, CreateTable, Delete, DropTable, Expr,
+    Insert, InsertSource, Join, JoinKind, OrderBy, Select, SelectItem, Statement, Update,
+};
+use crate::parser::parser::Parser;
+use crate::parser::token;
+
+// 语句的执行结果，select 返回列名和行，其余语句返回受影响的行数
+#[derive(Debug, Default, PartialEq)]
+pub struct ResultSet {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+    pub affected: usize,
+}
+
+// select 的结果按列对齐输出，其余语句只输出受影响的行数
+impl fmt::Display for ResultSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.columns.is_empty() {
+            return write!(f, "{} row(s) affected", self.affected);
+        }
+        let rows: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|row| row.iter().map(|value| value.to_string()).collect())
+            .collect();
+        let mut widths: Vec<usize> = self.columns.iter().map(|c| c.chars().count()).collect();
+        for row in rows.iter() {
+            for (width, text) in widths.iter_mut().zip(row.iter()) {
+                *width = (*width).max(text.chars().count());
+            }
+        }
+        let line = |f: &mut fmt::Formatter<'_>, cells: &[String]| -> fmt::Result {
+            let cells: Vec<String> = cells
+                .iter()
+                .zip(widths.iter())
+                .map(|(text, width)| format!("{:<width$}", text, width = width))
+                .collect();
+            writeln!(f, "{}", cells.join(" | ").trim_end())
+        };
+        line(f, &self.columns)?;
+        let rule: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+        writeln!(f, "{}", rule.join("-+-"))?;
+        for row in rows.iter() {
+            line(f, row)?;
+        }
+        write!(f, "({} row(s))", rows.len())
+    }
+}
+
+// char、varchar 主键不超过这个字节数时 key 和值的顺序一致，B+ 树的 key 还要用一个字节保存长度
+const KEY_STRING_SIZE: usize = 7;
+// 按 hash 得到 key 时，hash 的低 16 位清零后作为一个桶，hash 相同的值依次使用桶中的 key
+const KEY_BUCKET_MASK: usize = 0xffff;
+
+// 打开的一张表：<table>.frm 保存表结构，<table>.db 保存数据页，
+// 每个二级索引保存在 <table>.<index>.idx 中
+struct Table {
+    rowmanager: RowManager,
+    pagemanager: PageManager,
+    key: Option<usize>, // 主键列的位置
+    indexes: Vec<Index>,
+}
+
+impl Table {
+    fn new(schema: &Schema, pagemanager: PageManager, indexes: Vec<Index>) -> Self {
+        let key = schema
+            .primary_key
+            .as_ref()
+            .and_then(|key| schema.ptable.iter().position(|field| &field.value == key));
+        Table {
+            rowmanager: RowManager::with_fields(schema.ptable.clone()),
+            pagemanager,
+            key,
+            indexes,
+        }
+    }
+
+    fn field_names(&self) -> Vec<String> {
+        self.rowmanager
+            .fields()
+            .iter()
+            .map(|field| field.value.clone())
+            .collect()
+    }
+
+    fn columns(&self, qualifier: &str) -> Vec<Column> {
+        self.field_names()
+            .into_iter()
+            .map(|name| Column {
+                table: qualifier.to_string(),
+                name,
+            })
+            .collect()
+    }
+
+    // B+ 树的 key 对应的列，没有主键时 key 是隐藏的自增行号
+    fn key_column(&self) -> Option<usize> {
+        self.key
+    }
+
+    // 主键的 key 是否和值的顺序一致：整数和不超过 KEY_STRING_SIZE 个字节的 char、varchar
+    // 直接由值得到 key，其他类型的主键按值的 hash 得到 key
+    fn ordered_key(&self) -> bool {
+        self.key.is_some_and(|i| {
+            let field = &self.rowmanager.fields()[i];
+            field.is_integer()
+                || (matches!(field.fieldtype.as_str(), "char" | "varchar")
+                    && field.bitsize <= KEY_STRING_SIZE)
+        })
+    }
+
+    // 和 value 相等的主键所在的 key 的范围，有序的 key 只有一个，按 hash 得到的 key 是一个桶；
+    // 字符串按字节补 0 到 7 个字节，最后一个字节是长度，不同的字符串的 key 不同；
+    // char 保存时去掉了末尾的空白，末尾有空白的字符串不会等于任何记录；
+    // value 不能转换为主键的类型时返回 None，没有主键时也无法由列的值得到 key
+    fn key_bucket(&self, value: &Value) -> Option<(usize, usize)> {
+        let field = &self.rowmanager.fields()[self.key?];
+        if !self.ordered_key() {
+            // 按主键的类型编码后再 hash，相等的值编码相同
+            let bytes = RowManager::with_fields(vec![field.clone()])
+                .from_parser(&[], vec![value.clone()])
+                .ok()?;
+            let low = fnv_hash(&bytes) as usize & !KEY_BUCKET_MASK;
+            return Some((low, low | KEY_BUCKET_MASK));
+        }
+        let id = match value {
+            _ if field.is_integer() => index::integer_key(field, value)?,
+            Value::Str(s) if s.len() > KEY_STRING_SIZE => return None,
+            Value::Str(s) if field.fieldtype == "char" && s.trim_end() != s => return None,
+            Value::Str(s) => {
+                let mut buf = [0; 8];
+                buf[..s.len()].copy_from_slice(s.as_bytes());
+                buf[7] = s.len() as u8;
+                u64::from_be_bytes(buf) as usize
+            }
+            _ => return None,
+        };
+        Some((id, id))
+    }
+
+    // 新记录的 key：有序的 key 由主键的值得到，按 hash 得到的 key 取桶中第一个空位；
+    // 表中或者 pending 中已经有相等的主键时返回 DuplicateKey，replaced 中的记录会被替换，不参与比较
+    fn new_id(
+        &self,
+        value: &Value,
+        pending: &HashMap<usize, Value>,
+        replaced: &HashSet<usize>,
+    ) -> Result<usize, DbError> {
+        let key = self.key.unwrap();
+        let (low, high) = self.key_bucket(value).unwrap();
+        let mut taken: HashMap<usize, Value> = pending
+            .iter()
+            .filter(|(id, _)| (low..=high).contains(*id))
+            .map(|(id, value)| (*id, value.clone()))
+            .collect();
+        self.pagemanager.range(low, high, |id, data| {
+            if !replaced.contains(&id) {
+                taken.insert(id, self.decode(data.to_vec()).swap_remove(key));
+            }
+            true
+        });
+        if taken
+            .values()
+            .any(|other| other.compare(value) == Some(Ordering::Equal))
+        {
+            return Err(self.duplicate(value.clone()));
+        }
+        // 桶中的 key 都被占用时也当作重复
+        (low..=high)
+            .find(|id| !taken.contains_key(id))
+            .ok_or_else(|| self.duplicate(value.clone()))
+    }
+
+    fn duplicate(&self, value: Value) -> DbError {
+        DbError::DuplicateKey {
+            column: self.rowmanager.fields()[self.key.unwrap()].value.clone(),
+            value,
+        }
+    }
+
+    // 通过 B+ 树查找主键等于 value 的记录，value 无法得到 key 时返回 None
+    fn lookup(&self, value: &Value) -> Option<Vec<Vec<Value>>> {
+        let (low, high) = self.key_bucket(value)?;
+        let key = self.key?;
+        let mut rows = vec![];
+        self.pagemanager.range(low, high, |_, data| {
+            let values = self.decode(data.to_vec());
+            if values[key].compare(value) == Some(Ordering::Equal) {
+                rows.push(values);
+            }
+            true
+        });
+        Some(rows)
+    }
+
+    // 在 where 的 and 链中找 key 列 = 常量 或者 between 常量 and 常量 的条件
+    fn key_range(&self, columns: &[Column], expr: &Expr) -> Option<(usize, usize)> {
+        column_range(
+            &columns[self.key_column()?],
+            expr,
+            &|value| self.key_bucket(value),
+            self.ordered_key(),
+        )
+    }
+
+    // 主键上没有可用的条件时，找索引第一列上的条件，返回按顺序排列的候选记录的 key
+    fn index_range(&self, columns: &[Column], expr: &Expr) -> Option<Vec<usize>> {
+        self.indexes.iter().find_map(|index| {
+            let (low, high) = column_range(
+                &columns[index.columns[0]],
+                expr,
+                &|value| index.key(value).map(|key| (key, key)),
+                true,
+            )?;
+            let mut ids: Vec<usize> = index
+                .range(low, high)
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect();
+            ids.sort_unstable();
+            ids.dedup();
+            Some(ids)
+        })
+    }
+
+    fn all_rows(&self) -> Vec<Vec<Value>> {
+        self.pagemanager
+            .scan()
+            .into_iter()
+            .map(|(_, data)| self.decode(data))
+            .collect()
+    }
+
+    // 按 key 的顺序找出满足 where 条件的记录，找到 limit 条后停止扫描
+    fn filter(
+        &self,
+        context: Context,
+        columns: &[Column],
+        selection: &Option<Expr>,
+        limit: Option<usize>,
+    ) -> Result<Vec<(usize, Vec<Value>)>, DbError> {
+        let mut res = vec![];
+        if limit == Some(0) {
+            return Ok(res);
+        }
+        let keep = |values: &[Value]| match selection {
+            Some(expr) => eval::is_true(expr, &Scope::with(context, columns, values)),
+            None => Ok(true),
+        };
+        // key 列上的条件只需要扫描对应范围内的叶子页，否则用索引找出候选的记录，
+        // 其余条件仍然逐条判断
+        let range = selection
+            .as_ref()
+            .and_then(|expr| self.key_range(columns, expr));
+        let candidates = match (selection, range) {
+            (Some(expr), None) => self.index_range(columns, expr),
+            _ => None,
+        };
+        if let Some(ids) = candidates {
+            for id in ids {
+                let values = match self.pagemanager.select(id) {
+                    Some(data) => self.decode(data),
+                    None => continue,
+                };
+                if keep(&values)? {
+                    res.push((id, values));
+                    if limit.is_some_and(|limit| res.len() >= limit) {
+                        break;
+                    }
+                }
+            }
+            return Ok(res);
+        }
+        let (low, high) = range.unwrap_or((0, usize::MAX));
+        let mut err = None;
+        self.pagemanager.range(low, high, |id, data| {
+            let values = self.decode(data.to_vec());
+            match keep(&values) {
+                Ok(true) => res.push((id, values)),
+                Ok(false) => {}
+                Err(e) => {
+                    err = Some(e);
+                    return false;
+                }
+            }
+            limit.is_none_or(|limit| res.len() < limit)
+        });
+        match err {
+            Some(err) => Err(err),
+            None => Ok(res),
+        }
+    }
+
+    // 写入之前检查新的记录能否加入每个索引，唯一索引中的值不能和其他记录重复；
+    // replaced 中的记录会被新记录替换，不参与比较
+    fn check_indexes(
+        &self,
+        rows: &[(usize, Vec<Value>)],
+        replaced: &HashSet<usize>,
+    ) -> Result<(), DbError> {
+        for index in self.indexes.iter() {
+            check_index(index, rows, replaced)?;
+        }
+        Ok(())
+    }
+
+    fn index_insert(&mut self, id: usize, row: &[Value]) -> Result<(), DbError> {
+        for index in self.indexes.iter_mut() {
+            index.insert(id, row)?;
+        }
+        Ok(())
+    }
+
+    fn index_delete(&mut self, id: usize, row: &[Value]) {
+        for index in self.indexes.iter_mut() {
+            index.delete(id, row);
+        }
+    }
+
+    // text 和 blob 写入溢出页，不计入记录的长度
+    fn encode(&mut self, columns: &[String], values: Vec<Value>) -> Result<Vec<u8>, DbError> {
+        let bytes = self.rowmanager.from_parser(columns, values)?;
+        let len = self.rowmanager.stored_len(&bytes);
+        if len > MAX_ROW_SIZE {
+            return Err(DbError::RowTooLarge(len));
+        }
+        Ok(bytes)
+    }
+
+    fn decode(&self, data: Vec<u8>) -> Vec<Value> {
+        self.rowmanager.read(data, &self.pagemanager)
+    }
+
+    // 写入一条编码好的记录，先把 text 和 blob 写入溢出页
+    fn write(&mut self, id: usize, bytes: Vec<u8>) {
+        let bytes = self.rowmanager.spill(bytes, &mut self.pagemanager);
+        self.pagemanager.insert(id, bytes);
+    }
+
+    // 删除一条记录，同时释放它的溢出页
+    fn remove(&mut self, id: usize) {
+        if let Some(data) = self.pagemanager.delete(id) {
+            self.rowmanager.release(&data, &mut self.pagemanager);
+        }
+    }
+}
+
+// FNV-1a，用于由主键的值得到 key，结果要写入文件，不能用每次运行都不同的 DefaultHasher
+fn fnv_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// 在 where 的 and 链中找 column = 常量 或者 column between 常量 and 常量 的条件，
+// key 给出和常量相等的值在 B+ 树中的 key 的范围；ordered 为 false 时 key 和值的顺序无关，不能用于 between
+fn column_range(
+    column: &Column,
+    expr: &Expr,
+    key: &dyn Fn(&Value) -> Option<(usize, usize)>,
+    ordered: bool,
+) -> Option<(usize, usize)> {
+    match expr {
+        Expr::Binary {
+            left,
+            op: BinaryOp::And,
+            right,
+        } => column_range(column, left, key, ordered)
+            .or_else(|| column_range(column, right, key, ordered)),
+        Expr::Binary {
+            left,
+            op: BinaryOp::Eq,
+            right,
+        } => match (left.as_ref(), right.as_ref()) {
+            (expr, Expr::Literal(value)) | (Expr::Literal(value), expr)
+                if is_column(expr, column) =>
+            {
+                key(value)
+            }
+            _ => None,
+        },
+        Expr::Between {
+            expr,
+            low,
+            high,
+            negated: false,
+        } => match (low.as_ref(), high.as_ref()) {
+            (Expr::Literal(low), Expr::Literal(high)) if ordered && is_column(expr, column) => {
+                Some((key(low)?.0, key(high)?.1))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn check_index(
+    index: &Index,
+    rows: &[(usize, Vec<Value>)],
+    replaced: &HashSet<usize>,
+) -> Result<(), DbError> {
+    let mut seen = HashSet::new();
+    for (_, row) in rows.iter() {
+        index.check(row)?;
+        if !index.unique {
+            continue;
+        }
+        let values = index.values(row);
+        let duplicate = index.find(&values).iter().any(|id| !replaced.contains(id));
+        // 有 NULL 的值不会重复
+        let new = values.contains(&Value::Null) || seen.insert(format!("{:?}", values));
+        if duplicate || !new {
+            return Err(DbError::DuplicateEntry {
+                index: index.name.clone(),
+                values,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn remove_file(path: &str) -> Result<(), DbError> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            Err(DbError::Io(format!("{}: {}", path, err)))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn rename_file(from: &str, to: &str) -> Result<(), DbError> {
+    fs::rename(from, to).map_err(|err| DbError::Io(format!("{}: {}", from, err)))
+}
+
+// join 时查找右表中可能匹配的记录的方式，找到的记录还要再用 on 条件过滤
+enum Probe<'a> {
+    // 右边是右表的主键，用左边的值在 B+ 树中查找
+    Key(&'a Expr),
+    // 右边表达式的值到记录的 hash 表，表中所有值的类型相同
+    Hash {
+        left: &'a Expr,
+        valuetype: &'static str,
+        index: HashMap<String, Vec<usize>>,
+        rows: Vec<Vec<Value>>,
+    },
+    // 逐条比较
+    Scan(Vec<Vec<Value>>),
+}
+
+impl<'a> Probe<'a> {
+    fn hash(
+        context: Context,
+        left: &'a Expr,
+        right: &Expr,
+        columns: &[Column],
+        rows: Vec<Vec<Value>>,
+    ) -> Result<Self, DbError> {
+        let mut valuetype = None;
+        let mut index: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, values) in rows.iter().enumerate() {
+            let value = eval::eval(right, &Scope::with(context, columns, values))?;
+            if value.is_null() {
+                continue;
+            }
+            // 不同类型的值比较时会相互转换，浮点数也不适合作为 hash 的 key，这时逐条比较
+            match hash_key(&value) {
+                Some(key) if valuetype.is_none_or(|t| t == value.type_name()) => {
+                    valuetype = Some(value.type_name());
+                    index.entry(key).or_default().push(i);
+                }
+                _ => return Ok(Probe::Scan(rows)),
+            }
+        }
+        Ok(Probe::Hash {
+            left,
+            valuetype: valuetype.unwrap_or("null"),
+            index,
+            rows,
+        })
+    }
+
+    fn candidates(
+        &self,
+        table: &Table,
+        context: Context,
+        columns: &[Column],
+        values: &[Value],
+    ) -> Result<Vec<Vec<Value>>, DbError> {
+        Ok(match self {
+            Probe::Key(left) => {
+                let value = eval::eval(left, &Scope::with(context, columns, values))?;
+                match table.lookup(&value) {
+                    Some(rows) => rows,
+                    None if value.is_null() => vec![],
+                    // 值不能转换为主键的类型时逐条比较
+                    None => table.all_rows(),
+                }
+            }
+            Probe::Hash {
+                left,
+                valuetype,
+                index,
+                rows,
+            } => {
+                let value = eval::eval(left, &Scope::with(context, columns, values))?;
+                match hash_key(&value) {
+                    _ if value.is_null() => vec![],
+                    Some(key) if value.type_name() == *valuetype => index
+                        .get(&key)
+                        .map(|ids| ids.iter().map(|&i| rows[i].clone()).collect())
+                        .unwrap_or_default(),
+                    _ => rows.clone(),
+                }
+            }
+            Probe::Scan(rows) => rows.clone(),
+        })
+    }
+}
+
+fn hash_key(value: &Value) -> Option<String> {
+    match value {
+        Value::Int(n) => Some(n.to_string()),
+        Value::Str(s) => Some(s.clone()),
+        // 和字符串按字节比较，不是 UTF-8 的 blob 只能逐个比较
+        Value::Bytes(b) => String::from_utf8(b.clone()).ok(),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Date(_) | Value::Time(_) | Value::DateTime(_) => Some(value.to_string()),
+        // 小数位数不同的 decimal 可能相等
+        Value::Decimal(d) => Some(d.normalize().to_string()),
+        Value::Null | Value::Float(_) => None,
+    }
+}
+
+// 在 on 条件的 and 链中找一个 左表达式 = 右表达式 的条件，
+// 左边只用到左边的列，右边只用到右表的列
+fn equi_condition<'a>(
+    on: &'a Expr,
+    left_columns: &[Column],
+    right_columns: &[Column],
+) -> Option<(&'a Expr, &'a Expr)> {
+    match on {
+        Expr::Binary {
+            left,
+            op: BinaryOp::And,
+            right,
+        } => equi_condition(left, left_columns, right_columns)
+            .or_else(|| equi_condition(right, left_columns, right_columns)),
+        Expr::Binary {
+            left,
+            op: BinaryOp::Eq,
+            right,
+        } => {
+            if eval::covers(left_columns, left) && eval::covers(right_columns, right) {
+                Some((left, right))
+            } else if eval::covers(left_columns, right) && eval::covers(right_columns, left) {
+                Some((right, left))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+// 表达式是否引用了这一列
+fn is_column(expr: &Expr, column: &Column) -> bool {
+    match expr {
+        Expr::Column { table, name } => {
+            name == &column.name && table.as_ref().is_none_or(|t| t == &column.table)
+        }
+        _ => false,
+    }
+}
+
+// 结果中的列名，没有别名时用字段名或者聚合函数，如 count(*)、sum(age)
+fn column_name(expr: &Expr) -> String {
+    match expr {
+        Expr::Column { name, .. } => name.clone(),
+        Expr::Literal(value) => value.to_string(),
+        Expr::Aggregate { func, arg: None } => format!("{}(*)", func.name()),
+        Expr::Aggregate {
+            func,
+            arg: Some(arg),
+        } => format!("{}({})", func.name(), column_name(arg)),
+        _ => "?column?".to_string(),
+    }
+}
+
+// 按 group by 的值把记录分组，组的顺序是每组第一条记录出现的顺序
+fn group_rows(
+    context: Context,
+    columns: &[Column],
+    group_by: &[Expr],
+    rows: Vec<Vec<Value>>,
+) -> Result<Vec<Vec<Vec<Value>>>, DbError> {
+    let mut groups: Vec<Vec<Vec<Value>>> = vec![];
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for values in rows {
+        let scope = Scope::with(context, columns, &values);
+        let mut keys = vec![];
+        for expr in group_by.iter() {
+            keys.push(eval::eval(expr, &scope)?);
+        }
+        // Debug 的输出能区分类型，NULL 也会分到同一组
+        let key = format!("{:?}", keys);
+        match index.get(&key) {
+            Some(&i) => groups[i].push(values),
+            None => {
+                index.insert(key, groups.len());
+                groups.push(vec![values]);
+            }
+        }
+    }
+    Ok(groups)
+}
+
+// 聚合查询中先算出聚合函数的值，再在 scope 上求值
+fn eval_grouped(
+    expr: &Expr,
+    scope: &Scope,
+    columns: &[Column],
+    group: Option<&[Vec<Value>]>,
+) -> Result<Value, DbError> {
+    match group {
+        Some(rows) if expr.has_aggregate() => eval::eval(
+            &eval::resolve_aggregates(scope.context(), expr, columns, rows)?,
+            scope,
+        ),
+        _ => eval::eval(expr, scope),
+    }
+}
+
+// 排序时 NULL 最小，无法比较的值视为相等
+fn sort_order(a: &Value, b: &Value) -> Ordering {
+    match (a.is_null(), b.is_null()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => a.compare(b).unwrap_or(Ordering::Equal),
+    }
+}
+
+pub struct Executor {
+    dir: PathBuf,
+    database: Option<String>, // use 选中的数据库，没有选中时表在 dir 目录下
+}
+
+// 带有 ? 或者 $1 占位符的语句
+#[derive(Debug, Clone)]
+pub struct Prepared {
+    statement: Statement,
+    params: usize,
+}
+
+impl Prepared {
+    // 执行时需要的参数个数
+    pub fn params(&self) -> usize {
+        self.params
+    }
+}
+
+impl QueryRunner for Executor {
+    fn run(&self, select: &Select, outer: &Scope) -> Result<ResultSet, DbError> {
+        self.select(select, outer.nested())
+    }
+}
+
+impl Executor {
+    // 每个数据库是 dir 下的一个子目录，表保存在当前数据库的目录中
+    pub fn new(dir: &str) -> Self {
+        fs::create_dir_all(dir).unwrap();
+        Executor {
+            dir: PathBuf::from(dir),
+            database: None,
+        }
+    }
+
+    pub fn database(&self) -> Option<&str> {
+        self.database.as_deref()
+    }
+
+    pub fn use_database(&mut self, name: &str) -> Result<(), DbError> {
+        if !self.database_dir(name)?.is_dir() {
+            return Err(DbError::NoSuchDatabase(name.to_string()));
+        }
+        self.database = Some(name.to_string());
+        Ok(())
+    }
+
+    pub fn query(&mut self, sql: &str) -> Result<ResultSet, DbError> {
+        let prepared = self.prepare(sql)?;
+        self.execute_prepared(&prepared, &[])
+    }
+
+    // 只解析一次，之后可以用不同的参数多次执行
+    pub fn prepare(&self, sql: &str) -> Result<Prepared, DbError> {
+        let statement = Parser::new(token::tokenize(sql)).parse()?;
+        let mut params = 0;
+        statement.map_placeholders::<DbError>(&mut |i| {
+            params = params.max(i + 1);
+            Ok(Expr::Placeholder(i))
+        })?;
+        Ok(Prepared { statement, params })
+    }
+
+    // 参数直接替换为字面量，不会被当作 sql 解析
+    pub fn execute_prepared(
+        &mut self,
+        prepared: &Prepared,
+        params: &[Value],
+    ) -> Result<ResultSet, DbError> {
+        if params.len() != prepared.params {
+            return Err(DbError::ParameterCount {
+                expected: prepared.params,
+                found: params.len(),
+            });
+        }
+        let statement = prepared
+            .statement
+            .map_placeholders::<DbError>(&mut |i| Ok(Expr::Literal(params[i].clone())))?;
+        self.execute(statement)
+    }
+
+    // 按顺序执行脚本中的每条语句，遇到错误时停止
+    pub fn run_script(&mut self, sql: &str) -> Result<Vec<ResultSet>, DbError> {
+        let statements = Parser::new(token::tokenize(sql)).parse_script()?;
+        statements
+            .into_iter()
+            .map(|statement| self.execute(statement))
+            .collect()
+    }
+
+    pub fn run_file(&mut self, file_name: &str) -> Result<Vec<ResultSet>, DbError> {
+        let sql = fs::read_to_string(file_name)
+            .map_err(|err| DbError::Io(format!("{}: {}", file_name, err)))?;
+        self.run_script(&sql)
+    }
+
+    pub fn execute(&mut self, statement: Statement) -> Result<ResultSet, DbError> {
+        match statement {
+            Statement::CreateTable(create) => self.create_table(create),
+            Statement::Insert(insert) => self.insert(insert),
+            Statement::Select(select) => self.select(&select, Context::default()),
+            Statement::Update(update) => self.update(update),
+            Statement::Delete(delete) => self.delete(delete),
+            Statement::DropTable(drop) => self.drop_table(drop),
+            Statement::Truncate(table) => self.truncate(&table),
+            Statement::AlterTable(alter) => self.alter_table(alter),
+            Statement::CreateDatabase {
+                name,
+                if_not_exists,
+            } => self.create_database(&name, if_not_exists),
+            Statement::DropDatabase { name, if_exists } => self.drop_database(&name, if_exists),
+            Statement::Use(name) => {
+                self.use_database(&name)?;
+                Ok(ResultSet::default())
+            }
+            Statement::CreateIndex(create) => self.create_index(create),
+            Statement::DropIndex { name, table } => self.drop_index(&name, &table),
+        }
+    }
+
+    // 顶层语句求值的上下文，表达式中的子查询由 executor 执行
+    fn context(&self) -> Context<'_> {
+        Context {
+            outer: None,
+            runner: Some(self),
+        }
+    }
+
+    fn database_dir(&self, name: &str) -> Result<PathBuf, DbError> {
+        if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+            return Err(DbError::InvalidName(name.to_string()));
+        }
+        Ok(self.dir.join(name))
+    }
+
+    fn path(&self, table: &str, ext: &str) -> String {
+        let dir = match &self.database {
+            Some(database) => self.dir.join(database),
+            None => self.dir.clone(),
+        };
+        dir.join(format!("{}.{}", table, ext))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn index_path(&self, table: &str, index: &str) -> String {
+        self.path(table, &format!("{}.idx", index))
+    }
+
+    fn open(&self, table: &str) -> Result<Table, DbError> {
+        let frm = self.path(table, "frm");
+        if !PathBuf::from(&frm).exists() {
+            return Err(DbError::NoSuchTable(table.to_string()));
+        }
+        let schema = Schema::de(&frm);
+        let indexes = schema
+            .indexes
+            .iter()
+            .map(|index| {
+                let pagemanager = PageManager::read_file(&self.index_path(table, &index.name));
+                Index::new(index, &schema.ptable, pagemanager)
+            })
+            .collect();
+        Ok(Table::new(
+            &schema,
+            PageManager::read_file(&self.path(table, "db")),
+            indexes,
+        ))
+    }
+
+    fn create_table(&mut self, mut create: CreateTable) -> Result<ResultSet, DbError> {
+        let frm = self.path(&create.table, "frm");
+        if PathBuf::from(&frm).exists() {
+            return Err(DbError::TableExists(create.table));
+        }
+        // B+ 树的 key 由主键的值得到，主键不能为 NULL
+        if let Some(key) = &create.primary_key {
+            match create.fields.iter_mut().find(|field| &field.value == key) {
+                Some(field) => field.can_null = false,
+                None => return Err(DbError::NoSuchColumn(key.clone())),
+            }
+        }
+        Schema::new(&create).se(&frm);
+        PageManager::create(&self.path(&create.table, "db"));
+        Ok(ResultSet::default())
+    }
+
+    fn create_database(&mut self, name: &str, if_not_exists: bool) -> Result<ResultSet, DbError> {
+        let dir = self.database_dir(name)?;
+        if dir.is_dir() {
+            if if_not_exists {
+                return Ok(ResultSet::default());
+            }
+            return Err(DbError::DatabaseExists(name.to_string()));
+        }
+        fs::create_dir(&dir).map_err(|err| DbError::Io(format!("{}: {}", dir.display(), err)))?;
+        Ok(ResultSet::default())
+    }
+
+    // 删除数据库目录和其中所有的表，删除的是当前数据库时回到 dir 目录
+    fn drop_database(&mut self, name: &str, if_exists: bool) -> Result<ResultSet, DbError> {
+        let dir = self.database_dir(name)?;
+        if !dir.is_dir() {
+            if if_exists {
+                return Ok(ResultSet::default());
+            }
+            return Err(DbError::NoSuchDatabase(name.to_string()));
+        }
+        fs::remove_dir_all(&dir)
+            .map_err(|err| DbError::Io(format!("{}: {}", dir.display(), err)))?;
+        if self.database.as_deref() == Some(name) {
+            self.database = None;
+        }
+        Ok(ResultSet::default())
+    }
+
+    fn drop_table(&mut self, drop: DropTable) -> Result<ResultSet, DbError> {
+        let frm = self.path(&drop.table, "frm");
+        if !PathBuf::from(&frm).exists() {
+            if drop.if_exists {
+                return Ok(ResultSet::default());
+            }
+            return Err(DbError::NoSuchTable(drop.table));
+        }
+        let schema = Schema::de(&frm);
+        remove_file(&frm)?;
+        remove_file(&self.path(&drop.table, "db"))?;
+        for index in schema.indexes.iter() {
+            remove_file(&self.index_path(&drop.table, &index.name))?;
+        }
+        Ok(ResultSet::default())
+    }
+
+    // 重新建立空的数据文件和索引文件，表结构不变
+    fn truncate(&mut self, table: &str) -> Result<ResultSet, DbError> {
+        let indexes = self.open(table)?.indexes;
+        PageManager::create(&self.path(table, "db"));
+        for index in indexes.iter() {
+            PageManager::create(&self.index_path(table, &index.name));
+        }
+        Ok(ResultSet::default())
+    }
+
+    fn alter_table(&mut self, alter: AlterTable) -> Result<ResultSet, DbError> {
+        let table = self.open(&alter.table)?;
+        let names = table.field_names();
+        let mut schema = Schema::de(&self.path(&alter.table, "frm"));
+        match alter.operation {
+            AlterOperation::AddColumn(field) => {
+                if names.contains(&field.value) {
+                    return Err(DbError::ColumnExists(field.value));
+                }
+                // 新的列在已有的记录中都是 NULL
+                schema.ptable.push(field);
+                let rows = table
+                    .pagemanager
+                    .scan()
+                    .into_iter()
+                    .map(|(id, data)| {
+                        let mut values = table.decode(data);
+                        values.push(Value::Null);
+                        (id, values)
+                    })
+                    .collect();
+                self.rewrite(schema, rows)?;
+            }
+            AlterOperation::DropColumn(name) => {
+                let i = match names.iter().position(|n| n == &name) {
+                    Some(i) => i,
+                    None => return Err(DbError::NoSuchColumn(name)),
+                };
+                // 记录的 key 由主键得到，不能删除主键列
+                if schema.primary_key.as_ref() == Some(&name) {
+                    return Err(DbError::DropPrimaryKey(name));
+                }
+                if schema.ptable.len() == 1 {
+                    return Err(DbError::LastColumn(name));
+                }
+                schema.ptable.remove(i);
+                // 包含这一列的索引一起删除
+                let (dropped, kept): (Vec<IndexSchema>, Vec<IndexSchema>) = schema
+                    .indexes
+                    .into_iter()
+                    .partition(|index| index.columns.contains(&name));
+                schema.indexes = kept;
+                let rows = table
+                    .pagemanager
+                    .scan()
+                    .into_iter()
+                    .map(|(id, data)| {
+                        let mut values = table.decode(data);
+                        values.remove(i);
+                        (id, values)
+                    })
+                    .collect();
+                self.rewrite(schema, rows)?;
+                for index in dropped.iter() {
+                    remove_file(&self.index_path(&alter.table, &index.name))?;
+                }
+            }
+            // 记录的格式和列名无关，只需要修改表结构
+            AlterOperation::RenameColumn { from, to } => {
+                if names.contains(&to) {
+                    return Err(DbError::ColumnExists(to));
+                }
+                match schema.ptable.iter_mut().find(|field| field.value == from) {
+                    Some(field) => field.value = to.clone(),
+                    None => return Err(DbError::NoSuchColumn(from)),
+                }
+                for column in schema
+                    .indexes
+                    .iter_mut()
+                    .flat_map(|index| &mut index.columns)
+                {
+                    if column == &from {
+                        *column = to.clone();
+                    }
+                }
+                if schema.primary_key.as_ref() == Some(&from) {
+                    schema.primary_key = Some(to);
+                }
+                schema.se(&self.path(&alter.table, "frm"));
+            }
+            AlterOperation::RenameTable(name) => {
+                let frm = self.path(&name, "frm");
+                if PathBuf::from(&frm).exists() {
+                    return Err(DbError::TableExists(name));
+                }
+                rename_file(&self.path(&alter.table, "db"), &self.path(&name, "db"))?;
+                for index in schema.indexes.iter() {
+                    rename_file(
+                        &self.index_path(&alter.table, &index.name),
+                        &self.index_path(&name, &index.name),
+                    )?;
+                }
+                remove_file(&self.path(&alter.table, "frm"))?;
+                schema.table = name;
+                schema.se(&frm);
+            }
+        }
+        Ok(ResultSet::default())
+    }
+
+    // 按新的表结构重写所有记录，key 不变，所以保留下来的索引不需要重建
+    // 先写到临时文件，全部编码成功后再替换原来的数据文件和表结构
+    fn rewrite(&self, schema: Schema, rows: Vec<(usize, Vec<Value>)>) -> Result<(), DbError> {
+        let tmp = self.path(&schema.table, "db.tmp");
+        let names: Vec<String> = schema
+            .ptable
+            .iter()
+            .map(|field| field.value.clone())
+            .collect();
+        let mut new_table = Table::new(&schema, PageManager::create(&tmp), vec![]);
+        let mut batch = vec![];
+        for (id, values) in rows {
+            match new_table.encode(&names, values) {
+                Ok(bytes) => batch.push((id, bytes)),
+                Err(err) => {
+                    remove_file(&tmp)?;
+                    return Err(err);
+                }
+            }
+        }
+        for (id, bytes) in batch {
+            new_table.write(id, bytes);
+        }
+        rename_file(&tmp, &self.path(&schema.table, "db"))?;
+        schema.se(&self.path(&schema.table, "frm"));
+        Ok(())
+    }
+
+    // 用表中已有的记录建立索引
+    fn create_index(&mut self, create: CreateIndex) -> Result<ResultSet, DbError> {
+        let table = self.open(&create.table)?;
+        let frm = self.path(&create.table, "frm");
+        let mut schema = Schema::de(&frm);
+        if schema.indexes.iter().any(|index| index.name == create.name) {
+            return Err(DbError::IndexExists(create.name));
+        }
+        let names = table.field_names();
+        for column in create.columns.iter() {
+            if !names.contains(column) {
+                return Err(DbError::NoSuchColumn(column.clone()));
+            }
+        }
+        let index_schema = IndexSchema {
+            name: create.name,
+            columns: create.columns,
+            unique: create.unique,
+        };
+        // 索引的记录要放进一个页里，不能包含 text 和 blob
+        if let Some(field) = schema.ptable.iter().find(|field| {
+            index_schema.columns.contains(&field.value)
+                && matches!(field.fieldtype.as_str(), "text" | "blob")
+        }) {
+            return Err(DbError::InvalidIndexColumn(field.value.clone()));
+        }
+        let path = self.index_path(&create.table, &index_schema.name);
+        let mut index = Index::new(&index_schema, &schema.ptable, PageManager::create(&path));
+        let rows: Vec<(usize, Vec<Value>)> = table
+            .pagemanager
+            .scan()
+            .into_iter()
+            .map(|(id, data)| (id, table.decode(data)))
+            .collect();
+        if let Err(err) = check_index(&index, &rows, &HashSet::new()) {
+            remove_file(&path)?;
+            return Err(err);
+        }
+        for (id, row) in rows.iter() {
+            index.insert(*id, row)?;
+        }
+        schema.indexes.push(index_schema);
+        schema.se(&frm);
+        Ok(ResultSet::default())
+    }
+
+    fn drop_index(&mut self, name: &str, table: &str) -> Result<ResultSet, DbError> {
+        self.open(table)?;
+        let frm = self.path(table, "frm");
+        let mut schema = Schema::de(&frm);
+        let i = match schema.indexes.iter().position(|index| index.name == name) {
+            Some(i) => i,
+            None => return Err(DbError::NoSuchIndex(name.to_string())),
+        };
+        schema.indexes.remove(i);
+        schema.se(&frm);
+        remove_file(&self.index_path(table, name))?;
+        Ok(ResultSet::default())
+    }
+
+    fn insert(&mut self, insert: Insert) -> Result<ResultSet, DbError> {
+        let context = self.context();
+        let mut table = self.open(&insert.table)?;
+        let rows = match &insert.source {
+            InsertSource::Values(rows) => rows
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|expr| eval::eval(expr, &Scope::with(context, &[], &[])))
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            // 先读出所有结果再写入，insert into t select ... from t 不会读到新插入的行
+            InsertSource::Select(select) => self.select(select, context)?.rows,
+        };
+        // 所有行都编码成功后才写入，任何一行出错都不会插入数据
+        // 有主键时 key 由主键的值得到，否则用自增的行号
+        let mut last_id = table.pagemanager.max_id().unwrap_or(0);
+        let mut pending = HashMap::new();
+        let mut batch = vec![];
+        let mut new_rows = vec![];
+        for values in rows {
+            let bytes = table.encode(&insert.columns, values)?;
+            // key 由保存后的值得到，char 末尾的空白已经去掉
+            let row = table.rowmanager.to_row(bytes.clone());
+            let id = match table.key_column() {
+                Some(i) => {
+                    let id = table.new_id(&row[i], &pending, &HashSet::new())?;
+                    pending.insert(id, row[i].clone());
+                    id
+                }
+                None => {
+                    last_id += 1;
+                    last_id
+                }
+            };
+            batch.push((id, bytes));
+            new_rows.push((id, row));
+        }
+        table.check_indexes(&new_rows, &HashSet::new())?;
+        let affected = batch.len();
+        for (id, bytes) in batch {
+            table.write(id, bytes);
+        }
+        for (id, row) in new_rows.iter() {
+            table.index_insert(*id, row)?;
+        }
+        Ok(ResultSet {
+            affected,
+            ..Default::default()
+        })
+    }
+
+    // 只读取表文件，子查询也通过它执行
+    fn select(&self, select: &Select, outer: Context) -> Result<ResultSet, DbError> {
+        let context = Context {
+            runner: Some(self),
+            ..outer
+        };
+        let (table, mut columns) = match &select.from {
+            Some(from) => {
+                let table = self.open(&from.name)?;
+                let columns = table.columns(from.qualifier());
+                (Some(table), columns)
+            }
+            None => (None, vec![]),
+        };
+        let aggregate = !select.group_by.is_empty()
+            || select.having.is_some()
+            || select.items.iter().any(|item| match item {
+                SelectItem::Expr { expr, .. } => expr.has_aggregate(),
+                SelectItem::Wildcard => false,
+            })
+            || select.order_by.iter().any(|item| item.expr.has_aggregate());
+        // order by 和 B+ 树的 key 顺序一致时，扫描的结果已经有序，
+        // 这时 limit 可以提前结束扫描
+        let key_order = match (select.order_by.as_slice(), &table) {
+            ([], _) => Some(false),
+            _ if aggregate => None,
+            ([OrderBy { expr, desc }], Some(table)) => match table.key_column() {
+                Some(i) if table.ordered_key() && is_column(expr, &columns[i]) => Some(*desc),
+                _ => None,
+            },
+            _ => None,
+        };
+        let offset = select.offset.unwrap_or(0);
+        let scan_limit = match (key_order, select.limit) {
+            (Some(false), Some(limit)) if !aggregate && select.joins.is_empty() => {
+                Some(offset.saturating_add(limit))
+            }
+            _ => None,
+        };
+        // 有 join 时先连接，再用 where 过滤；连接保持左表的顺序
+        let selection = if select.joins.is_empty() {
+            &select.selection
+        } else {
+            &None
+        };
+        let mut rows = match &table {
+            Some(table) => table.filter(context, &columns, selection, scan_limit)?,
+            // 没有 from 时只有一行空记录
+            None => vec![(0, vec![])],
+        };
+        if key_order == Some(true) {
+            rows.reverse();
+        }
+        let mut rows: Vec<Vec<Value>> = rows.into_iter().map(|(_, values)| values).collect();
+        if !select.joins.is_empty() {
+            for join in select.joins.iter() {
+                let (joined_columns, joined_rows) = self.join(context, &columns, rows, join)?;
+                columns = joined_columns;
+                rows = joined_rows;
+            }
+            if let Some(expr) = &select.selection {
+                let mut kept = vec![];
+                for values in rows {
+                    if eval::is_true(expr, &Scope::with(context, &columns, &values))? {
+                        kept.push(values);
+                    }
+                }
+                rows = kept;
+            }
+        }
+        // 聚合查询的每一组用组内第一条记录求值非聚合的列，没有记录时都是 NULL
+        let groups = if aggregate {
+            let mut groups = group_rows(context, &columns, &select.group_by, rows)?;
+            if groups.is_empty() && select.group_by.is_empty() {
+                groups.push(vec![]);
+            }
+            groups
+                .into_iter()
+                .map(|group| {
+                    let first = match group.first() {
+                        Some(first) => first.clone(),
+                        None => vec![Value::Null; columns.len()],
+                    };
+                    (first, Some(group))
+                })
+                .collect()
+        } else {
+            rows.into_iter()
+                .map(|values| (values, None))
+                .collect::<Vec<_>>()
+        };
+
+        let mut res = ResultSet::default();
+        for item in select.items.iter() {
+            match item {
+                SelectItem::Wildcard => {
+                    res.columns
+                        .extend(columns.iter().map(|column| column.name.clone()));
+                }
+                SelectItem::Expr { expr, alias } => res.columns.push(match alias {
+                    Some(alias) => alias.clone(),
+                    None => column_name(expr),
+                }),
+            }
+        }
+        // order by 可以引用原表的列，也可以引用结果中的别名
+        let mut sort_columns = columns.clone();
+        sort_columns.extend(res.columns.iter().map(|name| Column {
+            table: String::new(),
+            name: name.clone(),
+        }));
+        let mut sorted = vec![];
+        for (values, group) in groups {
+            let group = group.as_deref();
+            let scope = Scope::with(context, &columns, &values);
+            if let Some(having) = &select.having {
+                if eval_grouped(having, &scope, &columns, group)?.to_bool() != Some(true) {
+                    continue;
+                }
+            }
+            let mut row = vec![];
+            for item in select.items.iter() {
+                match item {
+                    SelectItem::Wildcard => row.extend(values.iter().cloned()),
+                    SelectItem::Expr { expr, .. } => {
+                        row.push(eval_grouped(expr, &scope, &columns, group)?)
+                    }
+                }
+            }
+            let mut keys = vec![];
+            if key_order.is_none() {
+                let mut all = values;
+                all.extend(row.iter().cloned());
+                let scope = Scope::with(context, &sort_columns, &all);
+                for item in select.order_by.iter() {
+                    keys.push(eval_grouped(&item.expr, &scope, &columns, group)?);
+                }
+            }
+            sorted.push((keys, row));
+        }
+        if key_order.is_none() {
+            sorted.sort_by(|(a, _), (b, _)| {
+                for ((a, b), item) in a.iter().zip(b.iter()).zip(select.order_by.iter()) {
+                    let ordering = sort_order(a, b);
+                    let ordering = if item.desc {
+                        ordering.reverse()
+                    } else {
+                        ordering
+                    };
+                    if ordering != Ordering::Equal {
+                        return ordering;
+                    }
+                }
+                Ordering::Equal
+            });
+        }
+        res.rows = sorted
+            .into_iter()
+            .map(|(_, row)| row)
+            .skip(offset)
+            .take(select.limit.unwrap_or(usize::MAX))
+            .collect();
+        Ok(res)
+    }
+
+    // 左边的每条记录和右表中满足 on 条件的记录连接，left join 时没有匹配的记录补 NULL
+    fn join(
+        &self,
+        context: Context,
+        columns: &[Column],
+        rows: Vec<Vec<Value>>,
+        join: &Join,
+    ) -> Result<(Vec<Column>, Vec<Vec<Value>>), DbError> {
+        let right = self.open(&join.table.name)?;
+        let right_columns = right.columns(join.table.qualifier());
+        let mut all_columns = columns.to_vec();
+        all_columns.extend(right_columns.iter().cloned());
+
+        // 根据 on 中的等值条件选择查找右表记录的方式
+        let probe = match equi_condition(&join.on, columns, &right_columns) {
+            Some((left, right_expr)) => match right.key_column() {
+                // 条件列是右表的主键时直接在 B+ 树中查找
+                Some(i) if is_column(right_expr, &right_columns[i]) => Probe::Key(left),
+                _ => Probe::hash(context, left, right_expr, &right_columns, right.all_rows())?,
+            },
+            None => Probe::Scan(right.all_rows()),
+        };
+
+        let mut res = vec![];
+        for values in rows {
+            let candidates = probe.candidates(&right, context, columns, &values)?;
+            let mut matched = false;
+            for candidate in candidates {
+                let mut joined = values.clone();
+                joined.extend(candidate.iter().cloned());
+                if eval::is_true(&join.on, &Scope::with(context, &all_columns, &joined))? {
+                    matched = true;
+                    res.push(joined);
+                }
+            }
+            if !matched && join.kind == JoinKind::Left {
+                let mut joined = values;
+                joined.extend(vec![Value::Null; right_columns.len()]);
+                res.push(joined);
+            }
+        }
+        Ok((all_columns, res))
+    }
+
+    fn update(&mut self, update: Update) -> Result<ResultSet, DbError> {
+        let context = self.context();
+        let mut table = self.open(&update.table)?;
+        let names = table.field_names();
+        let columns = table.columns(&update.table);
+        for (name, _) in update.assignments.iter() {
+            if !names.contains(name) {
+                return Err(DbError::NoSuchColumn(name.clone()));
+            }
+        }
+        // 先算出所有新记录，全部成功后再写入
+        let mut rows = vec![];
+        for (id, values) in table.filter(context, &columns, &update.selection, None)? {
+            let scope = Scope::with(context, &columns, &values);
+            let mut new_values = values.clone();
+            for (name, expr) in update.assignments.iter() {
+                let i = names.iter().position(|n| n == name).unwrap();
+                new_values[i] = eval::eval(expr, &scope)?;
+            }
+            let bytes = table.encode(&names, new_values)?;
+            // 保存后的值，char 末尾的空白已经去掉
+            let new_values = table.rowmanager.to_row(bytes.clone());
+            rows.push((id, values, new_values, bytes));
+        }
+        // 修改后的记录重新分配 key，主键不能和其他记录重复；
+        // 修改的记录都会先删除，它们原来的 key 可以再次使用
+        let old_ids: HashSet<usize> = rows.iter().map(|row| row.0).collect();
+        let mut pending = HashMap::new();
+        let mut batch = vec![];
+        for (id, values, new_values, bytes) in rows {
+            let new_id = match table.key_column() {
+                Some(i) => {
+                    let new_id = table.new_id(&new_values[i], &pending, &old_ids)?;
+                    pending.insert(new_id, new_values[i].clone());
+                    new_id
+                }
+                None => id,
+            };
+            batch.push((id, values, new_id, new_values, bytes));
+        }
+        let new_rows: Vec<(usize, Vec<Value>)> = batch
+            .iter()
+            .map(|(_, _, new_id, new_values, _)| (*new_id, new_values.clone()))
+            .collect();
+        table.check_indexes(&new_rows, &old_ids)?;
+        for (id, values, new_id, _, _) in batch.iter() {
+            table.index_delete(*id, values);
+            if id != new_id {
+                table.remove(*id);
+            }
+        }
+        let affected = batch.len();
+        for (id, _, new_id, new_values, bytes) in batch {
+            if id == new_id {
+                table.remove(id);
+            }
+            table.write(new_id, bytes);
+            table.index_insert(new_id, &new_values)?;
+        }
+        Ok(ResultSet {
+            affected,
+            ..Default::default()
+        })
+    }
+
+    fn delete(&mut self, delete: Delete) -> Result<ResultSet, DbError> {
+        let context = self.context();
+        let mut table = self.open(&delete.table)?;
+        let columns = table.columns(&delete.table);
+        let rows = table.filter(context, &columns, &delete.selection, None)?;
+        for (id, values) in rows.iter() {
+            table.remove(*id);
+            table.index_delete(*id, values);
+        }
+        Ok(ResultSet {
+            affected: rows.len(),
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BinaryOp, DbError, Executor, Expr, Value};
+    use std::ops::{Deref, DerefMut};
+
+    // 测试结束时删除执行器的数据目录
+    struct TestExecutor(Executor);
+
+    impl Deref for TestExecutor {
+        type Target = Executor;
+
+        fn deref(&self) -> &Executor {
+            &self.0
+        }
+    }
+
+    impl DerefMut for TestExecutor {
+        fn deref_mut(&mut self) -> &mut Executor {
+            &mut self.0
+        }
+    }
+
+    impl Drop for TestExecutor {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0.dir);
+        }
+    }
+
+    fn executor(name: &str) -> TestExecutor {
+        let dir = std::env::temp_dir().join(format!("rs_sql_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        TestExecutor(Executor::new(dir.to_str().unwrap()))
+    }
+
+    fn ids(executor: &mut Executor, sql: &str) -> Vec<Value> {
+        executor
+            .query(sql)
+            .unwrap()
+            .rows
+            .into_iter()
+            .map(|row| row[0].clone())
+            .collect()
+    }
+
+    #[test]
+    fn where_clause() {
+        let mut executor = executor("where");
+        executor
+            .query("create table t (id int not null, name varchar(10), age int)")
+            .unwrap();
+        for sql in [
+            "insert into t values (1, 'a', 20)",
+            "insert into t values (2, 'b', 30)",
+            "insert into t values (3, null, 40)",
+            "insert into t values (4, 'd', null)",
+        ]
+        .iter()
+        {
+            assert_eq!(executor.query(sql).unwrap().affected, 1);
+        }
+        let int = |v: &[i64]| v.iter().map(|i| Value::Int(*i)).collect::<Vec<_>>();
+        assert_eq!(ids(&mut executor, "select id from t"), int(&[1, 2, 3, 4]));
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from t where age >= 30 and age < 40"
+            ),
+            int(&[2])
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from t where id = 1 or age > 30 and name = 'b'"
+            ),
+            int(&[1])
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from t where (id = 1 or age > 25) and name <> 'b'"
+            ),
+            int(&[1])
+        );
+        // NULL 参与比较的结果为 NULL，not NULL 仍为 NULL
+        assert_eq!(
+            ids(&mut executor, "select id from t where not age > 25"),
+            int(&[1])
+        );
+        assert_eq!(
+            ids(&mut executor, "select id from t where not (name != 'x')"),
+            int(&[])
+        );
+        let res = executor
+            .query("select name as n, age from t where id = 2")
+            .unwrap();
+        assert_eq!(res.columns, vec!["n".to_string(), "age".to_string()]);
+        assert_eq!(
+            res.rows,
+            vec![vec![Value::Str("b".to_string()), Value::Int(30)]]
+        );
+
+        assert!(matches!(
+            executor.query("select id from t where name > true"),
+            Err(DbError::Type(_))
+        ));
+        assert_eq!(
+            executor.query("select * from nope"),
+            Err(DbError::NoSuchTable("nope".to_string()))
+        );
+    }
+
+    #[test]
+    fn script() {
+        let mut executor = executor("script");
+        let file_name = concat!(env!("CARGO_MANIFEST_DIR"), "/sql/user.sql");
+        let results = executor.run_file(file_name).unwrap();
+        assert_eq!(results.len(), 4);
+        let res = executor
+            .run_script("select name from user where id >= 2; delete from user where id = 1;")
+            .unwrap();
+        assert_eq!(
+            res[0].rows,
+            vec![
+                vec![Value::Str("Bob Li".to_string())],
+                vec![Value::Str("O'Neil".to_string())],
+            ]
+        );
+        assert_eq!(res[1].affected, 1);
+        // 出错之前的语句已经执行
+        assert!(executor
+            .run_script("delete from user where id = 2; select * from nope")
+            .is_err());
+        assert_eq!(
+            ids(&mut executor, "select id from user"),
+            vec![Value::Int(3)]
+        );
+        assert!(matches!(
+            executor.run_file("no/such/file.sql"),
+            Err(DbError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn order_by_limit() {
+        let mut executor = executor("order_by");
+        executor
+            .run_script(
+                "create table t (id int not null, name varchar(10), age int);
+                insert into t values (1, 'd', 30);
+                insert into t values (2, 'b', null);
+                insert into t values (3, 'a', 20);
+                insert into t values (4, 'c', 30);
+                insert into t values (5, 'e', 10);",
+            )
+            .unwrap();
+        let int = |v: &[i64]| v.iter().map(|i| Value::Int(*i)).collect::<Vec<_>>();
+        assert_eq!(
+            ids(&mut executor, "select id from t order by name"),
+            int(&[3, 2, 4, 1, 5])
+        );
+        // NULL 排在最前，相等时保持 key 的顺序
+        assert_eq!(
+            ids(&mut executor, "select id from t order by age"),
+            int(&[2, 5, 3, 1, 4])
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from t order by age desc, name asc"
+            ),
+            int(&[4, 1, 3, 5, 2])
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id, age as a from t where age > 10 order by a desc, id desc limit 2"
+            ),
+            int(&[4, 1])
+        );
+        assert_eq!(
+            ids(&mut executor, "select id from t limit 2 offset 1"),
+            int(&[2, 3])
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from t order by name limit 10 offset 3"
+            ),
+            int(&[1, 5])
+        );
+        assert_eq!(ids(&mut executor, "select id from t limit 0"), int(&[]));
+    }
+
+    #[test]
+    fn aggregate() {
+        let mut executor = executor("aggregate");
+        executor
+            .run_script(
+                "create table emp (id int not null, dept varchar(10), age int);
+                insert into emp values (1, 'dev', 30);
+                insert into emp values (2, 'ops', 40);
+                insert into emp values (3, 'dev', 20);
+                insert into emp values (4, 'dev', null);
+                insert into emp values (5, null, 50);",
+            )
+            .unwrap();
+        let res = executor
+            .query("select count(*), count(age), sum(age), avg(age), min(age), max(dept) from emp")
+            .unwrap();
+        assert_eq!(
+            res.columns,
+            vec![
+                "count(*)",
+                "count(age)",
+                "sum(age)",
+                "avg(age)",
+                "min(age)",
+                "max(dept)"
+            ]
+        );
+        assert_eq!(
+            res.rows,
+            vec![vec![
+                Value::Int(5),
+                Value::Int(4),
+                Value::Int(140),
+                Value::Float(35.0),
+                Value::Int(20),
+                Value::Str("ops".to_string()),
+            ]]
+        );
+        let res = executor
+            .query(
+                "select dept, count(*) as n, sum(age) from emp group by dept order by n desc, dept",
+            )
+            .unwrap();
+        assert_eq!(
+            res.rows,
+            vec![
+                vec![Value::Str("dev".to_string()), Value::Int(3), Value::Int(50)],
+                vec![Value::Null, Value::Int(1), Value::Int(50)],
+                vec![Value::Str("ops".to_string()), Value::Int(1), Value::Int(40)],
+            ]
+        );
+        let res = executor
+            .query("select dept from emp where id > 1 group by dept having max(age) >= 40")
+            .unwrap();
+        assert_eq!(
+            res.rows,
+            vec![vec![Value::Str("ops".to_string())], vec![Value::Null]]
+        );
+        // 没有记录时 count 为 0，其余为 NULL
+        let res = executor
+            .query("select count(*), sum(age) from emp where id > 10")
+            .unwrap();
+        assert_eq!(res.rows, vec![vec![Value::Int(0), Value::Null]]);
+        let res = executor
+            .query("select dept, count(*) from emp where id > 10 group by dept")
+            .unwrap();
+        assert!(res.rows.is_empty());
+
+        assert!(matches!(
+            executor.query("select id from emp where count(*) > 1"),
+            Err(DbError::Type(_))
+        ));
+        assert!(matches!(
+            executor.query("select sum(dept) from emp"),
+            Err(DbError::Type(_))
+        ));
+    }
+
+    #[test]
+    fn join() {
+        let mut executor = executor("join");
+        executor
+            .run_script(
+                "create table users (id int not null, name varchar(10));
+                create table orders (id int not null, user_id int, amount int);
+                insert into users values (1, 'ann');
+                insert into users values (2, 'bob');
+                insert into users values (3, 'cat');
+                insert into orders values (10, 1, 5);
+                insert into orders values (11, 2, 7);
+                insert into orders values (12, 1, 9);
+                insert into orders values (13, null, 1);",
+            )
+            .unwrap();
+        let s = |v: &str| Value::Str(v.to_string());
+        let res = executor
+            .query(
+                "select u.name, o.id from users u join orders o on u.id = o.user_id \
+                 where o.amount > 5",
+            )
+            .unwrap();
+        assert_eq!(res.columns, vec!["name", "id"]);
+        assert_eq!(
+            res.rows,
+            vec![
+                vec![s("ann"), Value::Int(12)],
+                vec![s("bob"), Value::Int(11)],
+            ]
+        );
+        let res = executor
+            .query(
+                "select users.name, count(orders.id), sum(amount) from users \
+                 left join orders on orders.user_id = users.id and amount < 9 \
+                 group by users.name",
+            )
+            .unwrap();
+        assert_eq!(
+            res.rows,
+            vec![
+                vec![s("ann"), Value::Int(1), Value::Int(5)],
+                vec![s("bob"), Value::Int(1), Value::Int(7)],
+                vec![s("cat"), Value::Int(0), Value::Null],
+            ]
+        );
+        // 不是等值条件时逐条比较
+        let res = executor
+            .query("select a.id, b.id from users a join users b on a.id < b.id order by a.id, b.id")
+            .unwrap();
+        assert_eq!(
+            res.rows,
+            vec![
+                vec![Value::Int(1), Value::Int(2)],
+                vec![Value::Int(1), Value::Int(3)],
+                vec![Value::Int(2), Value::Int(3)],
+            ]
+        );
+        let res = executor
+            .query("select * from users join orders on users.id = orders.user_id limit 1")
+            .unwrap();
+        assert_eq!(res.columns, vec!["id", "name", "id", "user_id", "amount"]);
+        assert_eq!(
+            executor.query("select id from users join orders on users.id = user_id"),
+            Err(DbError::AmbiguousColumn("id".to_string()))
+        );
+        assert_eq!(
+            executor.query("select x.id from users u"),
+            Err(DbError::NoSuchColumn("x.id".to_string()))
+        );
+    }
+
+    #[test]
+    fn subquery() {
+        let mut executor = executor("subquery");
+        executor
+            .run_script(
+                "create table users (id int not null, name varchar(10));
+                create table orders (id int not null, user_id int, amount int);
+                insert into users values (1, 'ann');
+                insert into users values (2, 'bob');
+                insert into users values (3, 'cat');
+                insert into orders values (10, 1, 5);
+                insert into orders values (11, 3, 7);
+                insert into orders values (12, 1, 9);",
+            )
+            .unwrap();
+        let int = |v: &[i64]| v.iter().map(|i| Value::Int(*i)).collect::<Vec<_>>();
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from users where id in (select user_id from orders)"
+            ),
+            int(&[1, 3])
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from users u where exists (select * from orders o where o.user_id = u.id and amount > 6)"
+            ),
+            int(&[1, 3])
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from users u where not exists (select * from orders where user_id = u.id)"
+            ),
+            int(&[2])
+        );
+        let res = executor
+            .query(
+                "select name, (select sum(amount) from orders where user_id = users.id) as total \
+                 from users where id <> (select max(user_id) from orders)",
+            )
+            .unwrap();
+        assert_eq!(
+            res.rows,
+            vec![
+                vec![Value::Str("ann".to_string()), Value::Int(14)],
+                vec![Value::Str("bob".to_string()), Value::Null],
+            ]
+        );
+        // 子查询结果中有 NULL 时，not in 不成立
+        executor
+            .query("insert into orders (id, amount) values (13, 1)")
+            .unwrap();
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from users where id not in (select user_id from orders)"
+            ),
+            int(&[])
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from users where id not in (select user_id from orders where user_id > 0)"
+            ),
+            int(&[2])
+        );
+        executor
+            .query("delete from users where id in (select user_id from orders where amount = 7)")
+            .unwrap();
+        assert_eq!(ids(&mut executor, "select id from users"), int(&[1, 2]));
+        assert!(matches!(
+            executor.query("select (select id from orders) from users"),
+            Err(DbError::Type(_))
+        ));
+        assert!(matches!(
+            executor.query("select id from users where id in (select id, amount from orders)"),
+            Err(DbError::Type(_))
+        ));
+    }
+
+    #[test]
+    fn predicate() {
+        let mut executor = executor("predicate");
+        executor
+            .run_script(
+                "create table t (id int not null, name varchar(10));
+                insert into t values (1, 'apple');
+                insert into t values (2, 'banana');
+                insert into t values (3, '50%_off');
+                insert into t values (4, null);
+                insert into t values (5, 'grape');",
+            )
+            .unwrap();
+        let int = |v: &[i64]| v.iter().map(|i| Value::Int(*i)).collect::<Vec<_>>();
+        assert_eq!(
+            ids(&mut executor, "select id from t where id between 2 and 4"),
+            int(&[2, 3, 4])
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from t where id not between 2 and 4 and id in (1, 2, 5)"
+            ),
+            int(&[1, 5])
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from t where name in ('grape', 'apple', null)"
+            ),
+            int(&[1, 5])
+        );
+        assert_eq!(
+            ids(&mut executor, "select id from t where name like '%a_e'"),
+            int(&[5])
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from t where name like '%!%!_%' escape '!'"
+            ),
+            int(&[3])
+        );
+        assert_eq!(
+            ids(&mut executor, "select id from t where name not like '%an%'"),
+            int(&[1, 3, 5])
+        );
+    }
+
+    #[test]
+    fn update_delete() {
+        let mut executor = executor("update_delete");
+        executor
+            .query("create table t (id int not null, age int)")
+            .unwrap();
+        for i in 1..=50 {
+            executor
+                .query(&format!("insert into t values ({}, {})", i, i % 5))
+                .unwrap();
+        }
+        let res = executor
+            .query("update t set age = 9 where age = 0 or id <= 2")
+            .unwrap();
+        assert_eq!(res.affected, 12);
+        assert_eq!(
+            ids(&mut executor, "select id from t where age = 9").len(),
+            12
+        );
+        let res = executor.query("delete from t where not age = 9").unwrap();
+        assert_eq!(res.affected, 38);
+        assert_eq!(ids(&mut executor, "select id from t").len(), 12);
+        assert_eq!(
+            executor.query("update t set nope = 1"),
+            Err(DbError::NoSuchColumn("nope".to_string()))
+        );
+    }
+
+    #[test]
+    fn insert_rows() {
+        let mut executor = executor("insert_rows");
+        executor
+            .query("create table t (id int not null, name varchar(10))")
+            .unwrap();
+        let res = executor
+            .query("insert into t values (1, 'a'), (2, 'b'), (3, null)")
+            .unwrap();
+        assert_eq!(res.affected, 3);
+        assert_eq!(
+            ids(&mut executor, "select name from t"),
+            vec![
+                Value::Str("a".to_string()),
+                Value::Str("b".to_string()),
+                Value::Null
+            ]
+        );
+        // 有一行出错时整批都不插入
+        assert!(matches!(
+            executor.query("insert into t values (4, 'd'), ('5', 'e')"),
+            Err(DbError::TypeMismatch { .. })
+        ));
+        assert_eq!(
+            executor.query("insert into t (id, name) values (4)"),
+            Err(DbError::ValueCount {
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(ids(&mut executor, "select id from t").len(), 3);
+
+        executor
+            .query("create table s (name varchar(10), id int)")
+            .unwrap();
+        let res = executor
+            .query("insert into s (id, name) select id, name from t where id < 3")
+            .unwrap();
+        assert_eq!(res.affected, 2);
+        let res = executor.query("insert into s select * from s").unwrap();
+        assert_eq!(res.affected, 2);
+        assert_eq!(
+            ids(&mut executor, "select id from s"),
+            vec![Value::Int(1), Value::Int(2), Value::Int(1), Value::Int(2)]
+        );
+    }
+
+    #[test]
+    fn drop_alter() {
+        let mut executor = executor("drop_alter");
+        executor
+            .query("create table t (id int not null, name varchar(10))")
+            .unwrap();
+        executor
+            .query("insert into t values (1, 'a'), (2, 'b')")
+            .unwrap();
+
+        executor.query("alter table t add column age int").unwrap();
+        let res = executor.query("select * from t where id = 2").unwrap();
+        assert_eq!(res.columns, vec!["id", "name", "age"]);
+        assert_eq!(
+            res.rows,
+            vec![vec![
+                Value::Int(2),
+                Value::Str("b".to_string()),
+                Value::Null
+            ]]
+        );
+        executor.query("insert into t values (3, 'c', 30)").unwrap();
+        assert_eq!(
+            executor.query("alter table t add age int"),
+            Err(DbError::ColumnExists("age".to_string()))
+        );
+        // 已有记录的新列为 NULL，不能加 not null 的列
+        assert_eq!(
+            executor.query("alter table t add score int not null"),
+            Err(DbError::NotNull("score".to_string()))
+        );
+
+        executor.query("alter table t drop column name").unwrap();
+        assert_eq!(
+            executor.query("select * from t where id = 3").unwrap().rows,
+            vec![vec![Value::Int(3), Value::Int(30)]]
+        );
+        executor
+            .query("alter table t rename column age to years")
+            .unwrap();
+        assert_eq!(
+            ids(&mut executor, "select years from t"),
+            vec![Value::Null, Value::Null, Value::Int(30)]
+        );
+        executor.query("alter table t rename to s").unwrap();
+        assert_eq!(
+            executor.query("select * from t"),
+            Err(DbError::NoSuchTable("t".to_string()))
+        );
+        assert_eq!(ids(&mut executor, "select id from s").len(), 3);
+
+        executor.query("truncate table s").unwrap();
+        assert_eq!(ids(&mut executor, "select id from s").len(), 0);
+        executor.query("alter table s drop years").unwrap();
+        assert_eq!(
+            executor.query("alter table s drop id"),
+            Err(DbError::LastColumn("id".to_string()))
+        );
+
+        executor.query("drop table s").unwrap();
+        assert_eq!(
+            executor.query("drop table s"),
+            Err(DbError::NoSuchTable("s".to_string()))
+        );
+        executor.query("drop table if exists s").unwrap();
+        executor.query("create table s (id int)").unwrap();
+    }
+
+    #[test]
+    fn database() {
+        let mut executor = executor("database");
+        executor.query("create table t (id int)").unwrap();
+        executor.query("create database shop").unwrap();
+        assert_eq!(
+            executor.query("create database shop"),
+            Err(DbError::DatabaseExists("shop".to_string()))
+        );
+        executor
+            .query("create database if not exists shop")
+            .unwrap();
+        executor.query("use shop").unwrap();
+        assert_eq!(executor.database(), Some("shop"));
+        // 不同数据库中可以有同名的表
+        assert_eq!(
+            executor.query("select * from t"),
+            Err(DbError::NoSuchTable("t".to_string()))
+        );
+        executor.query("create table t (id int)").unwrap();
+        executor.query("insert into t values (1), (2)").unwrap();
+        assert_eq!(ids(&mut executor, "select id from t").len(), 2);
+
+        assert_eq!(
+            executor.query("use nope"),
+            Err(DbError::NoSuchDatabase("nope".to_string()))
+        );
+        assert_eq!(
+            executor.query("create database `../x`"),
+            Err(DbError::InvalidName("../x".to_string()))
+        );
+        executor.query("drop database shop").unwrap();
+        assert_eq!(executor.database(), None);
+        assert_eq!(ids(&mut executor, "select id from t").len(), 0);
+        assert_eq!(
+            executor.query("use shop"),
+            Err(DbError::NoSuchDatabase("shop".to_string()))
+        );
+        executor.query("drop database if exists shop").unwrap();
+    }
+
+    #[test]
+    fn primary_key() {
+        let mut executor = executor("primary_key");
+        executor
+            .query("create table t (name varchar(10), id int, primary key (id))")
+            .unwrap();
+        executor
+            .query("insert into t (id, name) values (3, 'c'), (1, 'a'), (2, 'b')")
+            .unwrap();
+        // 记录按主键的顺序保存
+        assert_eq!(
+            ids(&mut executor, "select id from t"),
+            vec![Value::Int(1), Value::Int(2), Value::Int(3)]
+        );
+        assert_eq!(
+            ids(&mut executor, "select name from t where id = 2"),
+            vec![Value::Str("b".to_string())]
+        );
+        let duplicate = Err(DbError::DuplicateKey {
+            column: "id".to_string(),
+            value: Value::Int(2),
+        });
+        assert_eq!(executor.query("insert into t values ('x', 2)"), duplicate);
+        assert_eq!(
+            executor.query("insert into t values ('x', 4), ('y', 4)"),
+            Err(DbError::DuplicateKey {
+                column: "id".to_string(),
+                value: Value::Int(4),
+            })
+        );
+        assert_eq!(
+            executor.query("insert into t (name) values ('x')"),
+            Err(DbError::NotNull("id".to_string()))
+        );
+        assert_eq!(ids(&mut executor, "select id from t").len(), 3);
+
+        // 修改主键后记录移到新的 key 下
+        assert_eq!(
+            executor.query("update t set id = 2 where id = 1"),
+            duplicate
+        );
+        executor.query("update t set id = 10 where id = 1").unwrap();
+        executor.query("update t set id = 1 where id = 2").unwrap();
+        assert_eq!(
+            ids(&mut executor, "select name from t order by id desc"),
+            vec![
+                Value::Str("a".to_string()),
+                Value::Str("c".to_string()),
+                Value::Str("b".to_string())
+            ]
+        );
+        assert_eq!(
+            ids(&mut executor, "select id from t where id between 2 and 10"),
+            vec![Value::Int(3), Value::Int(10)]
+        );
+
+        executor
+            .query("alter table t rename column id to no")
+            .unwrap();
+        assert_eq!(
+            executor.query("alter table t drop no"),
+            Err(DbError::DropPrimaryKey("no".to_string()))
+        );
+        executor.query("alter table t add age int").unwrap();
+        assert_eq!(
+            ids(&mut executor, "select name from t where no = 10"),
+            vec![Value::Str("a".to_string())]
+        );
+        // on 条件是右表的主键时在 B+ 树中查找
+        executor.query("create table u (tid int)").unwrap();
+        executor
+            .query("insert into u values (10), (4), (3)")
+            .unwrap();
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select t.name from u left join t on t.no = u.tid"
+            ),
+            vec![
+                Value::Str("a".to_string()),
+                Value::Null,
+                Value::Str("c".to_string())
+            ]
+        );
+        // 和主键相等的字符串也能查到，不能转换为整数的值逐条比较，结果和逐条比较相同
+        executor.query("create table q (ps varchar(5))").unwrap();
+        executor
+            .query("insert into q values ('3'), (' 10 '), ('3.5'), (null)")
+            .unwrap();
+        let (a, c) = (Value::Str("a".to_string()), Value::Str("c".to_string()));
+        assert_eq!(
+            ids(&mut executor, "select t.name from q join t on t.no = q.ps"),
+            vec![c, a.clone()]
+        );
+        assert_eq!(
+            ids(&mut executor, "select name from t where no = '10'"),
+            vec![a]
+        );
+    }
+
+    #[test]
+    fn string_key() {
+        let mut executor = executor("string_key");
+        let s = |v: &str| Value::Str(v.to_string());
+        executor
+            .run_script(
+                "create table v (code varchar(7), primary key (code));
+                insert into v values ('b'), ('ab'), ('a'), ('abcdefg'), ('');
+                create table c (code char(3) primary key, name varchar(5));
+                insert into c values ('x  ', 'one');",
+            )
+            .unwrap();
+        // 前缀排在更长的字符串前面
+        assert_eq!(
+            ids(&mut executor, "select code from v"),
+            vec![s(""), s("a"), s("ab"), s("abcdefg"), s("b")]
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select code from v where code between 'a' and 'abz'"
+            ),
+            vec![s("a"), s("ab"), s("abcdefg")]
+        );
+        assert_eq!(
+            executor.query("insert into v values ('ab')"),
+            Err(DbError::DuplicateKey {
+                column: "code".to_string(),
+                value: s("ab"),
+            })
+        );
+        // char 的 key 由去掉末尾空白后的值得到
+        assert_eq!(
+            executor.query("insert into c values ('x', 'two')"),
+            Err(DbError::DuplicateKey {
+                column: "code".to_string(),
+                value: s("x"),
+            })
+        );
+        assert_eq!(
+            ids(&mut executor, "select name from c where code = 'x'"),
+            vec![s("one")]
+        );
+        assert!(ids(&mut executor, "select name from c where code = 'x '").is_empty());
+        executor
+            .query("update c set code = 'ab ' where code = 'x'")
+            .unwrap();
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select c.name from v join c on c.code = v.code"
+            ),
+            vec![s("one")]
+        );
+    }
+
+    #[test]
+    fn hashed_key() {
+        let mut executor = executor("hashed_key");
+        let s = |v: &str| Value::Str(v.to_string());
+        // 超过 7 个字节的字符串主键按 hash 得到 key
+        executor
+            .run_script(
+                "create table h (code varchar(20) primary key, n int);
+                insert into h values ('pear-and-apple', 1), ('banana', 2), ('apple-and-pear', 3);
+                create table o (code varchar(20), m int);
+                insert into o values ('banana', 20), ('cherry', 30), ('pear-and-apple', 10);",
+            )
+            .unwrap();
+        assert_eq!(
+            executor.query("insert into h values ('banana', 4)"),
+            Err(DbError::DuplicateKey {
+                column: "code".to_string(),
+                value: s("banana"),
+            })
+        );
+        assert_eq!(
+            executor.query("insert into h values ('kiwi', 4), ('kiwi', 5)"),
+            Err(DbError::DuplicateKey {
+                column: "code".to_string(),
+                value: s("kiwi"),
+            })
+        );
+        assert_eq!(
+            ids(&mut executor, "select n from h where code = 'banana'"),
+            vec![Value::Int(2)]
+        );
+        assert!(ids(&mut executor, "select n from h where code = 'bananas'").is_empty());
+        // key 和值的顺序无关，order by 和 between 仍然按值比较
+        assert_eq!(
+            ids(&mut executor, "select code from h order by code limit 2"),
+            vec![s("apple-and-pear"), s("banana")]
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select n from h where code between 'b' and 'q' order by n"
+            ),
+            vec![Value::Int(1), Value::Int(2)]
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select h.n from o left join h on h.code = o.code"
+            ),
+            vec![Value::Int(2), Value::Null, Value::Int(1)]
+        );
+        // 修改主键后按新的值查找，没有修改主键的记录留在原来的 key 下
+        executor
+            .run_script(
+                "update h set code = 'cherry' where n = 3;
+                update h set n = 5 where code = 'banana';",
+            )
+            .unwrap();
+        assert_eq!(
+            ids(&mut executor, "select n from h where code = 'cherry'"),
+            vec![Value::Int(3)]
+        );
+        assert!(ids(
+            &mut executor,
+            "select n from h where code = 'apple-and-pear'"
+        )
+        .is_empty());
+        assert_eq!(
+            ids(&mut executor, "select n from h where code = 'banana'"),
+            vec![Value::Int(5)]
+        );
+        assert_eq!(
+            executor.query("update h set code = 'banana' where n = 1"),
+            Err(DbError::DuplicateKey {
+                column: "code".to_string(),
+                value: s("banana"),
+            })
+        );
+        // 很多记录时每条记录都能通过主键查到
+        for i in 0..200 {
+            executor
+                .query(&format!(
+                    "insert into h values ('key-{:04}', {})",
+                    i,
+                    i + 10
+                ))
+                .unwrap();
+        }
+        assert_eq!(
+            ids(&mut executor, "select n from h where code = 'key-0123'"),
+            vec![Value::Int(133)]
+        );
+        assert_eq!(ids(&mut executor, "select n from h").len(), 203);
+    }
+
+    #[test]
+    fn index() {
+        let mut executor = executor("index");
+        executor
+            .query("create table t (id int primary key, name varchar(10), age int)")
+            .unwrap();
+        for i in 1..=40 {
+            executor
+                .query(&format!(
+                    "insert into t values ({}, 'n{}', {})",
+                    i,
+                    i,
+                    i % 4
+                ))
+                .unwrap();
+        }
+        executor.query("create index idx_age on t (age)").unwrap();
+        executor
+            .query("create unique index idx_name on t (name)")
+            .unwrap();
+        assert_eq!(
+            executor.query("create index idx_age on t (id)"),
+            Err(DbError::IndexExists("idx_age".to_string()))
+        );
+        assert_eq!(
+            executor.query("create index idx_nope on t (nope)"),
+            Err(DbError::NoSuchColumn("nope".to_string()))
+        );
+        assert!(matches!(
+            executor.query("create unique index idx_dup on t (age)"),
+            Err(DbError::DuplicateEntry { .. })
+        ));
+
+        // where 中索引第一列上的条件通过索引查找
+        let table = executor.open("t").unwrap();
+        let columns = table.columns("t");
+        let expr = Expr::binary(
+            Expr::column("age"),
+            BinaryOp::Eq,
+            Expr::Literal(Value::Int(3)),
+        );
+        assert_eq!(table.index_range(&columns, &expr).unwrap().len(), 10);
+        drop(table);
+        assert_eq!(
+            ids(&mut executor, "select id from t where age = 3 and id < 12"),
+            vec![Value::Int(3), Value::Int(7), Value::Int(11)]
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from t where name between 'n37' and 'n39'"
+            ),
+            vec![Value::Int(37), Value::Int(38), Value::Int(39)]
+        );
+
+        // 插入、修改、删除时索引保持同步
+        assert_eq!(
+            executor.query("insert into t values (41, 'n1', 0)"),
+            Err(DbError::DuplicateEntry {
+                index: "idx_name".to_string(),
+                values: vec![Value::Str("n1".to_string())],
+            })
+        );
+        assert!(matches!(
+            executor.query("update t set name = 'n2' where id = 1"),
+            Err(DbError::DuplicateEntry { .. })
+        ));
+        executor
+            .query("update t set age = 9 where age = 3")
+            .unwrap();
+        assert_eq!(
+            ids(&mut executor, "select id from t where age = 3").len(),
+            0
+        );
+        assert_eq!(
+            ids(&mut executor, "select id from t where age = 9").len(),
+            10
+        );
+        executor
+            .query("update t set name = 'x', id = 100 where name = 'n5'")
+            .unwrap();
+        assert_eq!(
+            ids(&mut executor, "select id from t where name = 'x'"),
+            vec![Value::Int(100)]
+        );
+        executor.query("delete from t where age = 9").unwrap();
+        assert_eq!(
+            ids(&mut executor, "select id from t where age = 9").len(),
+            0
+        );
+        executor.query("insert into t values (3, 'n3', 1)").unwrap();
+        assert_eq!(
+            ids(&mut executor, "select id from t where name = 'n3'"),
+            vec![Value::Int(3)]
+        );
+
+        executor.query("alter table t rename to s").unwrap();
+        assert_eq!(
+            ids(&mut executor, "select id from s where age = 1 and id < 10"),
+            vec![Value::Int(1), Value::Int(3), Value::Int(9)]
+        );
+        executor.query("alter table s drop column age").unwrap();
+        assert_eq!(
+            executor.query("drop index idx_age on s"),
+            Err(DbError::NoSuchIndex("idx_age".to_string()))
+        );
+        executor.query("truncate s").unwrap();
+        executor.query("insert into s values (1, 'n1')").unwrap();
+        assert_eq!(
+            ids(&mut executor, "select id from s where name = 'n1'"),
+            vec![Value::Int(1)]
+        );
+        executor.query("drop index idx_name on s").unwrap();
+        executor.query("insert into s values (2, 'n1')").unwrap();
+    }
+
+    #[test]
+    fn prepared() {
+        let mut executor = executor("prepared");
+        executor
+            .query("create table t (id int primary key, name varchar(20))")
+            .unwrap();
+        let insert = executor.prepare("insert into t values (?, ?)").unwrap();
+        assert_eq!(insert.params(), 2);
+        for (id, name) in [(1, "a"), (2, "x'; drop table t"), (3, "c")] {
+            executor
+                .execute_prepared(&insert, &[id.into(), name.into()])
+                .unwrap();
+        }
+        executor
+            .execute_prepared(&insert, &[4.into(), None::<&str>.into()])
+            .unwrap();
+        // 参数只是值，不会被当作 sql 解析
+        let select = executor
+            .prepare("select id from t where name = $1 or id = $2")
+            .unwrap();
+        assert_eq!(
+            executor
+                .execute_prepared(&select, &["x'; drop table t".into(), 3.into()])
+                .unwrap()
+                .rows,
+            vec![vec![Value::Int(2)], vec![Value::Int(3)]]
+        );
+        let exists = executor
+            .prepare("select count(*) from t where exists (select id from t where id > ?)")
+            .unwrap();
+        assert_eq!(
+            executor
+                .execute_prepared(&exists, &[3.into()])
+                .unwrap()
+                .rows,
+            vec![vec![Value::Int(4)]]
+        );
+        assert_eq!(
+            executor.execute_prepared(&exists, &[]),
+            Err(DbError::ParameterCount {
+                expected: 1,
+                found: 0
+            })
+        );
+        assert_eq!(
+            executor.query("delete from t where id = ?"),
+            Err(DbError::ParameterCount {
+                expected: 1,
+                found: 0
+            })
+        );
+        let delete = executor.prepare("delete from t where id = ?").unwrap();
+        assert_eq!(
+            executor
+                .execute_prepared(&delete, &[1.into()])
+                .unwrap()
+                .affected,
+            1
+        );
+        assert_eq!(ids(&mut executor, "select id from t").len(), 3);
+    }
+
+    #[test]
+    fn temporal() {
+        let mut executor = executor("temporal");
+        executor
+            .query("create table e (id int primary key, d date, t time, dt datetime, ts timestamp)")
+            .unwrap();
+        executor
+            .query(
+                "insert into e values \
+                 (1, '2024-02-29', '09:30', '2024-02-29 18:00:00', '2024-02-29T18:00:00+08:00'), \
+                 (2, date '2023-12-31', time '23:59:59.5', '2023-12-31', '2023-12-31T23:00:00Z'), \
+                 (3, '2024-03-01', '00:00:00', datetime '2024-03-01 08:00', null)",
+            )
+            .unwrap();
+        let text = |executor: &mut Executor, sql: &str| -> Vec<String> {
+            executor.query(sql).unwrap().rows[0]
+                .iter()
+                .map(|v| v.to_string())
+                .collect()
+        };
+        assert_eq!(
+            text(&mut executor, "select d, t, dt, ts from e where id = 2"),
+            vec![
+                "2023-12-31",
+                "23:59:59.5",
+                "2023-12-31 00:00:00",
+                "2023-12-31 23:00:00"
+            ]
+        );
+        // 按时间先后比较和排序，字符串按字段的类型解析
+        assert_eq!(
+            ids(&mut executor, "select id from e order by d desc"),
+            vec![Value::Int(3), Value::Int(1), Value::Int(2)]
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from e where d between '2024-01-01' and date '2024-02-29'"
+            ),
+            vec![Value::Int(1)]
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from e where dt < '2024-03-01' and t > '09:00'"
+            ),
+            vec![Value::Int(1), Value::Int(2)]
+        );
+        // 带时区的 timestamp 按 UTC 保存
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from e where ts = '2024-02-29 10:00:00'"
+            ),
+            vec![Value::Int(1)]
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from e where ts = '2024-02-29 18:00:00+08:00'"
+            ),
+            vec![Value::Int(1)]
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from e where ts > '2024-02-29T00:00:00Z'"
+            ),
+            vec![Value::Int(1)]
+        );
+        assert_eq!(
+            ids(&mut executor, "select id from e where d = dt"),
+            vec![Value::Int(2)]
+        );
+        assert_eq!(
+            text(&mut executor, "select min(d), max(t) from e"),
+            vec!["2023-12-31", "23:59:59.5"]
+        );
+        executor.query("create index idx_d on e (d)").unwrap();
+        assert_eq!(
+            ids(&mut executor, "select id from e where d = '2024-03-01'"),
+            vec![Value::Int(3)]
+        );
+
+        assert!(matches!(
+            executor.query("insert into e (id, d) values (4, '2024-02-30')"),
+            Err(DbError::TypeMismatch { .. })
+        ));
+        assert!(matches!(
+            executor.query("insert into e (id, dt) values (4, '2024-02-01 10:00+01:00')"),
+            Err(DbError::TypeMismatch { .. })
+        ));
+        assert!(matches!(
+            executor.query("insert into e (id, t) values (4, 930)"),
+            Err(DbError::TypeMismatch { .. })
+        ));
+        assert!(matches!(
+            executor.query("select id from e where d < 5"),
+            Err(DbError::Type(_))
+        ));
+    }
+
+    #[test]
+    fn decimal() {
+        let mut executor = executor("decimal");
+        executor
+            .query(
+                "create table bill (id int primary key, amount decimal(10, 2), big decimal(30, 4))",
+            )
+            .unwrap();
+        executor
+            .query(
+                "insert into bill values \
+                 (1, 19.99, '12345678901234567890.1234'), \
+                 (2, '0.105', -1), \
+                 (3, 10, decimal '0.00005'), \
+                 (4, null, null)",
+            )
+            .unwrap();
+        let text = |executor: &mut Executor, sql: &str| -> Vec<String> {
+            executor
+                .query(sql)
+                .unwrap()
+                .rows
+                .iter()
+                .flatten()
+                .map(|v| v.to_string())
+                .collect()
+        };
+        // 按字段的小数位数四舍五入
+        assert_eq!(
+            text(&mut executor, "select amount, big from bill where id < 4"),
+            vec![
+                "19.99",
+                "12345678901234567890.1234",
+                "0.11",
+                "-1.0000",
+                "10.00",
+                "0.0001"
+            ]
+        );
+        // sum 和 avg 是精确的，avg 多保留 4 位小数
+        assert_eq!(
+            text(
+                &mut executor,
+                "select sum(amount), avg(amount), sum(big) from bill"
+            ),
+            vec!["30.10", "10.033333", "12345678901234567889.1235"]
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from bill where big > '12345678901234567890.1233'"
+            ),
+            vec![Value::Int(1)]
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from bill where amount = 0.11 or amount = '10'"
+            ),
+            vec![Value::Int(2), Value::Int(3)]
+        );
+        assert_eq!(
+            ids(&mut executor, "select id from bill order by amount desc"),
+            vec![Value::Int(1), Value::Int(3), Value::Int(2), Value::Int(4)]
+        );
+        executor
+            .query("create index idx_amount on bill (amount)")
+            .unwrap();
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from bill where amount between 0.1 and 10"
+            ),
+            vec![Value::Int(2), Value::Int(3)]
+        );
+
+        assert!(matches!(
+            executor.query("insert into bill (id, amount) values (5, 123456789.5)"),
+            Err(DbError::OutOfRange { .. })
+        ));
+        assert!(matches!(
+            executor.query("insert into bill (id, amount) values (5, 'abc')"),
+            Err(DbError::TypeMismatch { .. })
+        ));
+
+        // 没有引号的小数也是精确的，超过 17 位有效数字时不会按浮点数舍入
+        executor
+            .query(
+                "insert into bill (id, big) values \
+                 (6, 12345678901234567.8912), (7, 12345678901234567890123456.1234)",
+            )
+            .unwrap();
+        assert_eq!(
+            text(&mut executor, "select big from bill where id > 5"),
+            vec!["12345678901234567.8912", "12345678901234567890123456.1234"]
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from bill where big = 12345678901234567890123456.1234"
+            ),
+            vec![Value::Int(7)]
+        );
+    }
+
+    #[test]
+    fn integer() {
+        let mut executor = executor("integer");
+        executor
+            .query("create table n (id smallint primary key, small tinyint unsigned, big bigint)")
+            .unwrap();
+        executor
+            .query(
+                "insert into n values (-300, 255, -9223372036854775808), (7, 0, 1), (-1, 1, null)",
+            )
+            .unwrap();
+        // 负数的主键按值的顺序保存在 B+ 树中
+        assert_eq!(
+            ids(&mut executor, "select id from n"),
+            vec![Value::Int(-300), Value::Int(-1), Value::Int(7)]
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select big from n where id between -300 and -1"
+            ),
+            vec![Value::Int(i64::MIN), Value::Null]
+        );
+        assert_eq!(
+            ids(&mut executor, "select sum(small) from n"),
+            vec![Value::Int(256)]
+        );
+        assert_eq!(
+            executor.query("insert into n values (40000, 0, 0)"),
+            Err(DbError::OutOfRange {
+                column: "id".to_string(),
+                value: Value::Int(40000)
+            })
+        );
+        assert_eq!(
+            executor.query("update n set small = -1 where id = 7"),
+            Err(DbError::OutOfRange {
+                column: "small".to_string(),
+                value: Value::Int(-1)
+            })
+        );
+
+        // bigint unsigned 的值可以到 u64 的最大值，超出 i64 的值按 decimal 读出和比较
+        executor
+            .run_script(
+                "create table u (id bigint unsigned primary key, n bigint unsigned);
+                insert into u values (18446744073709551615, 18446744073709551615), \
+                (9223372036854775808, 2), (1, 0);",
+            )
+            .unwrap();
+        let text = |executor: &mut Executor, sql: &str| -> Vec<String> {
+            ids(executor, sql).iter().map(|v| v.to_string()).collect()
+        };
+        assert_eq!(
+            text(&mut executor, "select id from u"),
+            vec!["1", "9223372036854775808", "18446744073709551615"]
+        );
+        assert_eq!(
+            text(
+                &mut executor,
+                "select n from u where id = 18446744073709551615"
+            ),
+            vec!["18446744073709551615"]
+        );
+        assert_eq!(
+            text(
+                &mut executor,
+                "select id from u where n > 9223372036854775807 or n = 0"
+            ),
+            vec!["1", "18446744073709551615"]
+        );
+        assert_eq!(
+            text(&mut executor, "select max(n) from u"),
+            vec!["18446744073709551615"]
+        );
+        assert!(matches!(
+            executor.query("insert into u values (18446744073709551616, 0)"),
+            Err(DbError::OutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn float() {
+        let mut executor = executor("float");
+        executor
+            .query("create table m (id int primary key, f float, d double, ok boolean)")
+            .unwrap();
+        executor
+            .query(
+                "insert into m values (1, 0.1, 0.1, true), (2, -2.5, 1e300, false), \
+                 (3, 1, -.5, 1), (4, null, null, null)",
+            )
+            .unwrap();
+        let prepared = executor
+            .prepare("insert into m values (5, ?, ?, false)")
+            .unwrap();
+        executor
+            .execute_prepared(&prepared, &[Value::Float(f64::NAN), Value::Float(f64::NAN)])
+            .unwrap();
+        // float 列保存的是 f32，读出时仍然等于写入的 0.1
+        assert_eq!(
+            ids(&mut executor, "select id from m where f = 0.1"),
+            vec![Value::Int(1)]
+        );
+        // NaN 和 NaN 相等，并且大于其他所有数字
+        assert_eq!(
+            ids(&mut executor, "select id from m where d > 1"),
+            vec![Value::Int(2), Value::Int(5)]
+        );
+        let prepared = executor.prepare("select id from m where d = ?").unwrap();
+        let nan = executor
+            .execute_prepared(&prepared, &[Value::Float(f64::NAN)])
+            .unwrap();
+        assert_eq!(nan.rows, vec![vec![Value::Int(5)]]);
+        assert_eq!(
+            ids(&mut executor, "select id from m order by d"),
+            vec![
+                Value::Int(4),
+                Value::Int(3),
+                Value::Int(1),
+                Value::Int(2),
+                Value::Int(5)
+            ]
+        );
+        assert_eq!(
+            ids(&mut executor, "select id from m where ok"),
+            vec![Value::Int(1), Value::Int(3)]
+        );
+        assert_eq!(
+            ids(&mut executor, "select id from m where ok = false"),
+            vec![Value::Int(2), Value::Int(5)]
+        );
+        // 索引中的顺序和比较的顺序一致
+        executor.query("create index idx_d on m (d)").unwrap();
+        executor.query("create index idx_ok on m (ok)").unwrap();
+        assert_eq!(
+            ids(&mut executor, "select id from m where d between -1 and 1"),
+            vec![Value::Int(1), Value::Int(3)]
+        );
+        assert_eq!(
+            ids(&mut executor, "select id from m where d = -0.5"),
+            vec![Value::Int(3)]
+        );
+        assert_eq!(
+            ids(&mut executor, "select id from m where ok = true"),
+            vec![Value::Int(1), Value::Int(3)]
+        );
+
+        assert!(matches!(
+            executor.query("insert into m (id, f) values (6, 1e39)"),
+            Err(DbError::OutOfRange { .. })
+        ));
+        assert!(matches!(
+            executor.query("insert into m (id, ok) values (6, 2)"),
+            Err(DbError::TypeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn overflow() {
+        let mut executor = executor("overflow");
+        executor
+            .query("create table doc (id int primary key, title varchar(10), body text, data blob)")
+            .unwrap();
+        let body = |n: usize| "0123456789".repeat(n);
+        let prepared = executor
+            .prepare("insert into doc values (?, ?, ?, ?)")
+            .unwrap();
+        for id in 1..=3 {
+            executor
+                .execute_prepared(
+                    &prepared,
+                    &[
+                        Value::Int(id),
+                        Value::from(format!("doc{}", id)),
+                        Value::Str(body(id as usize * 100)),
+                        Value::Bytes(vec![id as u8; 300]),
+                    ],
+                )
+                .unwrap();
+        }
+        executor
+            .query("insert into doc values (4, 'short', 'abc', x'00FF')")
+            .unwrap();
+        // 超过 255 个字节的值也能完整读出
+        assert_eq!(
+            ids(&mut executor, "select body from doc where id = 3"),
+            vec![Value::Str(body(300))]
+        );
+        assert_eq!(
+            ids(&mut executor, "select data from doc where id = 4"),
+            vec![Value::Bytes(vec![0, 0xff])]
+        );
+        assert_eq!(
+            ids(
+                &mut executor,
+                "select id from doc where body like '%789' order by body desc"
+            ),
+            vec![Value::Int(3), Value::Int(2), Value::Int(1)]
+        );
+        executor
+            .query(&format!(
+                "update doc set body = '{}' where id = 1",
+                body(50)
+            ))
+            .unwrap();
+        executor.query("delete from doc where id = 2").unwrap();
+        executor
+            .query("alter table doc add column note varchar(5)")
+            .unwrap();
+
+        // 重新打开表，溢出页中的值不变
+        let mut executor = Executor::new(executor.dir.to_str().unwrap());
+        assert_eq!(
+            ids(&mut executor, "select body from doc"),
+            vec![
+                Value::Str(body(50)),
+                Value::Str(body(300)),
+                Value::Str("abc".to_string())
+            ]
+        );
+        assert_eq!(
+            ids(&mut executor, "select data from doc where id = 3"),
+            vec![Value::Bytes(vec![3; 300])]
+        );
+        assert_eq!(
+            executor.query("create index idx_body on doc (title, body)"),
+            Err(DbError::InvalidIndexColumn("body".to_string()))
+        );
+    }
+}
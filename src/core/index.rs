@@ -0,0 +1,182 @@
+use std::convert::TryFrom;
+
+use super::error::DbError;
+use super::page::{PageManager, MAX_ROW_SIZE};
+use super::row::RowManager;
+use super::schema::IndexSchema;
+use super::temporal;
+use super::value::Value;
+use crate::parser::ast::Field;
+
+// 二级索引，也是一棵 B+ 树，把索引列的值映射到记录在表中的 key
+// 索引的 key 由第一列的值得到，不同的值可能得到相同的 key，所以 key 可以重复；
+// 每条记录是表中的 key 加上按索引列编码的值，查找时再比较实际的值
+pub(crate) struct Index {
+    pub name: String,
+    pub columns: Vec<usize>, // 索引列在表中的位置
+    pub unique: bool,
+    field: Field, // 第一列
+    rowmanager: RowManager,
+    pub pagemanager: PageManager,
+}
+
+impl Index {
+    pub fn new(schema: &IndexSchema, fields: &[Field], pagemanager: PageManager) -> Self {
+        let columns: Vec<usize> = schema
+            .columns
+            .iter()
+            .map(|name| {
+                fields
+                    .iter()
+                    .position(|field| &field.value == name)
+                    .unwrap()
+            })
+            .collect();
+        // 第一列之外的索引列可以为 NULL
+        let index_fields = columns
+            .iter()
+            .map(|&i| Field {
+                can_null: true,
+                ..fields[i].clone()
+            })
+            .collect();
+        Index {
+            name: schema.name.clone(),
+            unique: schema.unique,
+            field: fields[columns[0]].clone(),
+            columns,
+            rowmanager: RowManager::with_fields(index_fields),
+            pagemanager,
+        }
+    }
+
+    // 值对应的索引 key，key 的顺序和值的顺序一致；
+    // 整数、decimal、日期和时间翻转符号位，字符串取前 8 个字节，值的类型和第一列不一致时没有 key
+    // 浮点数正数翻转符号位、负数翻转所有位，-0 和 0 相同，NaN 在最后
+    pub fn key(&self, value: &Value) -> Option<usize> {
+        let flip = |v: i64| Some(((v as u64) ^ (1 << 63)) as usize);
+        match (self.field.fieldtype.as_str(), value) {
+            _ if self.field.is_integer() => integer_key(&self.field, value),
+            // 按字段的小数位数取整，超出 i64 的部分并到两端
+            ("decimal", value) => {
+                let v = value
+                    .as_decimal()?
+                    .rescale(self.field.scale as u32)?
+                    .value();
+                flip(v.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+            }
+            ("float" | "double", Value::Int(_) | Value::Float(_) | Value::Decimal(_)) => {
+                let mut v = value.as_f64()?;
+                // float 列保存的是 f32，按读出时的值计算 key
+                if self.field.fieldtype == "float" {
+                    v = (v as f32).to_string().parse().ok()?;
+                }
+                let v = if v.is_nan() { f64::NAN } else { v + 0.0 };
+                let bits = v.to_bits();
+                let bits = if bits >> 63 == 1 {
+                    !bits
+                } else {
+                    bits | 1 << 63
+                };
+                Some(bits as usize)
+            }
+            ("boolean", Value::Bool(b)) => Some(*b as usize),
+            (fieldtype, value) if temporal::is_temporal(fieldtype) => {
+                match temporal::convert(fieldtype, value)? {
+                    Value::Date(days) => flip(days as i64),
+                    Value::Time(v) | Value::DateTime(v) => flip(v),
+                    _ => None,
+                }
+            }
+            ("char" | "varchar", Value::Str(v)) => {
+                let mut buf = [0; 8];
+                for (b, c) in buf.iter_mut().zip(v.bytes()) {
+                    *b = c;
+                }
+                Some(u64::from_be_bytes(buf) as usize)
+            }
+            _ => None,
+        }
+    }
+
+    // 表中一行对应的索引值
+    pub fn values(&self, row: &[Value]) -> Vec<Value> {
+        self.columns.iter().map(|&i| row[i].clone()).collect()
+    }
+
+    // 记录的格式：表中的 key（8 个字节）+ 索引列的值
+    // 第一列为 NULL 的行不会被 = 或者 between 查到，不放进索引
+    fn entry(&self, id: usize, row: &[Value]) -> Result<Option<(usize, Vec<u8>)>, DbError> {
+        let values = self.values(row);
+        let key = match self.key(&values[0]) {
+            Some(key) => key,
+            None => return Ok(None),
+        };
+        let mut data = id.to_ne_bytes().to_vec();
+        data.append(&mut self.rowmanager.from_parser(&[], values)?);
+        if data.len() > MAX_ROW_SIZE {
+            return Err(DbError::RowTooLarge(data.len()));
+        }
+        Ok(Some((key, data)))
+    }
+
+    // 检查能否为这一行建立索引，不写入
+    pub fn check(&self, row: &[Value]) -> Result<(), DbError> {
+        self.entry(0, row).map(|_| ())
+    }
+
+    pub fn insert(&mut self, id: usize, row: &[Value]) -> Result<(), DbError> {
+        if let Some((key, data)) = self.entry(id, row)? {
+            self.pagemanager.insert(key, data);
+        }
+        Ok(())
+    }
+
+    pub fn delete(&mut self, id: usize, row: &[Value]) {
+        if let Ok(Some((key, data))) = self.entry(id, row) {
+            self.pagemanager
+                .delete_where(key, |entry| entry == data.as_slice());
+        }
+    }
+
+    // key 在 low 和 high 之间的记录，返回表中的 key 和索引列的值
+    pub fn range(&self, low: usize, high: usize) -> Vec<(usize, Vec<Value>)> {
+        let mut res = vec![];
+        self.pagemanager.range(low, high, |_, data| {
+            let mut buf = [0; 8];
+            buf.copy_from_slice(&data[..8]);
+            let values = self.rowmanager.to_row(data[8..].to_vec());
+            res.push((usize::from_ne_bytes(buf), values));
+            true
+        });
+        res
+    }
+
+    // 索引值和 values 完全相同的记录在表中的 key，唯一索引用它检查重复；
+    // 有 NULL 的值不和任何值重复
+    pub fn find(&self, values: &[Value]) -> Vec<usize> {
+        if values.contains(&Value::Null) {
+            return vec![];
+        }
+        let key = match self.key(&values[0]) {
+            Some(key) => key,
+            None => return vec![],
+        };
+        self.range(key, key)
+            .into_iter()
+            .filter(|(_, entry)| entry == values)
+            .map(|(id, _)| id)
+            .collect()
+    }
+}
+
+// 整数列的 key，翻转符号位后负数排在正数前面，bigint unsigned 直接用 u64 的值；
+// decimal、浮点数和字符串等于某个整数时按这个整数计算，和整数列相等的值都得到同一个 key
+pub(crate) fn integer_key(field: &Field, value: &Value) -> Option<usize> {
+    let v = value.as_integer()?;
+    if field.unsigned && field.bitsize == 8 {
+        return u64::try_from(v).ok().map(|v| v as usize);
+    }
+    let v = i64::try_from(v).ok()?;
+    Some(((v as u64) ^ (1 << 63)) as usize)
+}
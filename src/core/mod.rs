@@ -1,2 +1,10 @@
+pub mod decimal;
+pub mod error;
+pub mod eval;
+pub mod executor;
+pub mod index;
 pub mod page;
 pub mod row;
+pub mod schema;
+pub mod temporal;
+pub mod value;
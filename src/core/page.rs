@@ -13,6 +13,16 @@ use super::super::bptree::bptree::*;
 
 const PAGE_SIZE: usize = 152;
 const HEADER_SIZE: usize = size_of::<FileHeader>() + size_of::<PageHeader>();
+// 一条记录的数据部分最多能有多长，超过后即使单独放在一个页里也放不下
+pub const MAX_ROW_SIZE: usize = PAGE_SIZE - HEADER_SIZE - size_of::<usize>() * 2;
+// page_index_id 在页中的位置
+const PAGE_INDEX_ID_OFFSET: usize = size_of::<usize>() * 6;
+// 溢出页和空闲页的 page_leval，不属于 B+ 树，page_index_id 为 NO_FATHER
+const OVERFLOW_PAGE: usize = usize::MAX;
+const FREE_PAGE: usize = usize::MAX - 1;
+const NO_FATHER: usize = usize::MAX;
+// 一个溢出页能保存的字节数
+const OVERFLOW_CAPACITY: usize = PAGE_SIZE - HEADER_SIZE;
 
 #[derive(Debug, Clone)]
 struct FileHeader {
@@ -107,6 +117,35 @@ impl IndexPage {
         }
         s
     }
+
+    // 重新计算每条索引的 next 以及页头
+    fn relayout(&mut self) {
+        let mut offset = self.fileheader.file_page_offset * PAGE_SIZE + HEADER_SIZE;
+        self.pageheader.page_heap_top = offset;
+        for row in self.indexrecord.row.iter_mut() {
+            offset += size_of::<usize>() * 3;
+            row.next = offset;
+        }
+        self.pageheader.page_last_insert = offset;
+        self.pageheader.page_n_heap = self.indexrecord.row.len();
+    }
+
+    fn is_full(&self) -> bool {
+        self.pageheader.page_last_insert > (self.fileheader.file_page_offset + 1) * PAGE_SIZE
+    }
+
+    // 第一条索引的 id 当作负无穷，其余每条索引的 id 是对应子页中最小的 id
+    // 找到最后一个 id <= 目标 id 的子页
+    fn child(&self, id: usize) -> usize {
+        let mut pos = self.indexrecord.row[0].pos;
+        for row in &self.indexrecord.row[1..] {
+            if row.id > id {
+                break;
+            }
+            pos = row.pos;
+        }
+        pos
+    }
 }
 #[derive(Debug, Clone)]
 struct DataPage {
@@ -179,26 +218,28 @@ impl DataPage {
     }
 
     pub fn push(&mut self, id: usize, data: Vec<u8>) {
-        let mut start_row_offset = self.pageheader.page_heap_top;
-        let next_offset = self.pageheader.page_last_insert;
-        let new_len = size_of::<usize>() * 2 + data.len();
-        self.datarecord.row.push(RowData {
-            next: (next_offset + new_len),
-            id,
-            data: data,
-        });
-        self.datarecord.row.sort_by_key(|row| row.id);
+        // 按 id 排序，id 相同时新记录放在后面
+        let pos = self.datarecord.row.partition_point(|row| row.id <= id);
         self.datarecord
             .row
-            .iter_mut()
-            .enumerate()
-            .for_each(|(i, row)| {
-                row.next = start_row_offset + size_of::<usize>() * 2 + row.data.len();
-                start_row_offset += size_of::<usize>() * 2 + row.data.len();
-            });
-        // 重置某些属性
-        self.pageheader.page_last_insert = start_row_offset;
-        self.pageheader.page_n_heap += 1;
+            .insert(pos, RowData { next: 0, id, data });
+        self.relayout();
+    }
+
+    // 重新计算每条记录的 next 以及页头
+    fn relayout(&mut self) {
+        let mut offset = self.fileheader.file_page_offset * PAGE_SIZE + HEADER_SIZE;
+        self.pageheader.page_heap_top = offset;
+        for row in self.datarecord.row.iter_mut() {
+            offset += size_of::<usize>() * 2 + row.data.len();
+            row.next = offset;
+        }
+        self.pageheader.page_last_insert = offset;
+        self.pageheader.page_n_heap = self.datarecord.row.len();
+    }
+
+    fn is_full(&self) -> bool {
+        self.pageheader.page_last_insert > (self.fileheader.file_page_offset + 1) * PAGE_SIZE
     }
 }
 
@@ -212,6 +253,7 @@ pub(crate) struct PageManager {
     pub f: File,
     pub max_page_id: usize,
     pub root_page_id: usize,
+    free_pages: Vec<usize>, // 释放的溢出页，分配新页时优先使用
 }
 impl PageManager {
     pub fn create(file_name: &str) -> Self {
@@ -226,16 +268,22 @@ impl PageManager {
             f,
             max_page_id: 0,
             root_page_id: 0,
+            free_pages: vec![],
         }
     }
 
     pub fn read_file(filename: &str) -> Self {
         if let Ok(f) = OpenOptions::new().read(true).write(true).open(filename) {
             let max_page_id = Self::get_max_page_id(&f);
+            let root_page_id = Self::get_root_page_id(&f, max_page_id);
+            let free_pages = (0..=max_page_id)
+                .filter(|&i| Self::read_header(&f, i).is_some_and(|header| header[5] == FREE_PAGE))
+                .collect();
             PageManager {
                 f,
                 max_page_id,
-                root_page_id: 0,
+                root_page_id,
+                free_pages,
             }
         } else {
             Self::create(filename)
@@ -423,7 +471,10 @@ impl PageManager {
             println!("");
             let level = vec[5];
             // 判断节点类型，读取 record
-            if level > 0 {
+            if level == OVERFLOW_PAGE || level == FREE_PAGE {
+                // 溢出页和空闲页
+                println!("overflow: next {} len {}", vec[2], vec[3]);
+            } else if level > 0 {
                 // 是 branch 节点，有 next, id, pos,
                 let node_n: u64 = vec[3].try_into().unwrap();
                 for i in 0..(node_n) {
@@ -462,101 +513,104 @@ impl PageManager {
         }
     }
 
+    // 读取页头的 7 个数字，页不存在时返回 None
+    fn read_header(f: &File, page_id: usize) -> Option<[usize; 7]> {
+        let mut buf = [0; HEADER_SIZE];
+        match f.read_at(&mut buf, (page_id * PAGE_SIZE) as u64) {
+            Ok(n) if n == HEADER_SIZE => {}
+            _ => return None,
+        }
+        let mut header = [0; 7];
+        for (i, chunk) in buf.chunks(size_of::<usize>()).enumerate() {
+            header[i] = usize::from_ne_bytes(chunk.try_into().unwrap());
+        }
+        // file_page_next 为 0 说明这个页还没有被使用
+        if header[1] == 0 {
+            return None;
+        }
+        Some(header)
+    }
+
     fn get_max_page_id(f: &File) -> usize {
-        // 读取两页
         let mut max_page_id = 0;
-        for i in 0..64 {
-            println!("-------------------------------");
-            let start_offset = i * PAGE_SIZE as u64;
-            let mut buf = [0; 8];
-            let mut sbuf = [0; 1024];
-
-            // 读取前 7 个数字
-            let mut vec = vec![];
-            for i in 0..7 {
-                f.read_at(&mut buf, start_offset + i * 8).unwrap();
-                print!("{:?} ", usize::from_ne_bytes(buf));
-                vec.push(usize::from_ne_bytes(buf));
-            }
-
-            if vec[1] == 0 {
-                break;
-            }
+        while Self::read_header(f, max_page_id).is_some() {
             max_page_id += 1;
         }
-        max_page_id - 1
+        max_page_id.saturating_sub(1)
     }
 
-    fn get_page(&self, i: u64) -> Option<PageType> {
-        let f = &self.f;
-        let start_offset = i * PAGE_SIZE as u64;
-        let mut buf = [0; 8];
-        let mut sbuf = [0; 1024];
-        let mut vec = vec![];
-        for i in 0..7 {
-            match f.read_at(&mut buf, start_offset + i * 8) {
-                Ok(_) => {}
-                Err(e) => {
-                    return None;
-                    break;
+    // 根页的 page_index_id 指向自己
+    fn get_root_page_id(f: &File, max_page_id: usize) -> usize {
+        for i in 0..=max_page_id {
+            if let Some(header) = Self::read_header(f, i) {
+                if header[6] == i {
+                    return i;
                 }
             }
-            print!("{:?} ", usize::from_ne_bytes(buf));
-            vec.push(usize::from_ne_bytes(buf));
-        }
-        if vec[1] == 0 {
-            return None;
         }
+        0
+    }
+
+    fn get_page(&self, i: u64) -> Option<PageType> {
+        let page_id = i as usize;
+        let vec = Self::read_header(&self.f, page_id)?;
+        let start_offset = page_id * PAGE_SIZE;
+        let mut page = [0; PAGE_SIZE];
+        self.f.read_at(&mut page, start_offset as u64).ok()?;
+        let read_usize = |offset: usize| -> usize {
+            let offset = offset - start_offset;
+            usize::from_ne_bytes(page[offset..offset + 8].try_into().unwrap())
+        };
+
         let fileheader: FileHeader = FileHeader::new(vec[0], vec[1]);
         let pageheader: PageHeader = PageHeader::new(vec[2], vec[3], vec[4], vec[5], vec[6]);
         let level = vec[5];
         if level > 0 {
             let mut indexrecord = IndexRecord { row: vec![] };
-            let node_n: u64 = vec[3].try_into().unwrap();
-            for i in 0..(node_n) {
-                f.read_at(&mut buf, start_offset + HEADER_SIZE as u64 + i * 24 + 8 * 0)
-                    .unwrap();
-                let next = usize::from_ne_bytes(buf);
-                f.read_at(&mut buf, start_offset + HEADER_SIZE as u64 + i * 24 + 8 * 1)
-                    .unwrap();
-                let id = usize::from_ne_bytes(buf);
-                f.read_at(&mut buf, start_offset + HEADER_SIZE as u64 + i * 24 + 8 * 2)
-                    .unwrap();
-                let pos = usize::from_ne_bytes(buf);
-                indexrecord.row.push(RowIndex { next, id, pos });
+            for i in 0..vec[3] {
+                let row_start = start_offset + HEADER_SIZE + i * 24;
+                if row_start + 24 > start_offset + PAGE_SIZE {
+                    break;
+                }
+                indexrecord.row.push(RowIndex {
+                    next: read_usize(row_start),
+                    id: read_usize(row_start + 8),
+                    pos: read_usize(row_start + 16),
+                });
             }
             let indexpage = IndexPage {
                 fileheader,
                 pageheader,
                 indexrecord,
             };
-            return Some(PageType::Index(indexpage));
+            Some(PageType::Index(indexpage))
         } else {
             let mut datarecord: DataRecord = DataRecord { row: vec![] };
-            let mut row_start: u64 = start_offset + HEADER_SIZE as u64;
-            for i in 0..vec[3] {
-                f.read_at(&mut buf, row_start);
-                let row_next: usize = usize::from_ne_bytes(buf);
-                let row_next_u64: u64 = u64::from_ne_bytes(buf);
-                let row_start_usize: usize = row_start.try_into().unwrap();
-                let len: usize = (row_next - row_start_usize);
-                f.read_at(&mut buf, row_start + 8 * 1);
-                let id = usize::from_ne_bytes(buf);
-                f.read_at(&mut sbuf, row_start + 8 * 2);
-                // let s = String::from_utf8_lossy(&sbuf[0..len - 16]).to_string();
+            let mut row_start = start_offset + HEADER_SIZE;
+            for _ in 0..vec[3] {
+                if row_start + 16 > start_offset + PAGE_SIZE {
+                    break;
+                }
+                let row_next = read_usize(row_start);
+                if row_next < row_start + 16 || row_next > start_offset + PAGE_SIZE {
+                    break;
+                }
+                let id = read_usize(row_start + 8);
+                let data_start = row_start + 16 - start_offset;
+                let data_end = row_next - start_offset;
                 datarecord.row.push(RowData {
                     next: row_next,
                     id,
-                    data: sbuf[0..len - 16].to_vec(),
+                    data: page[data_start..data_end].to_vec(),
                 });
-                row_start = row_next_u64;
+                row_start = row_next;
             }
             let datapage = DataPage {
                 fileheader,
                 pageheader,
                 datarecord,
             };
-            return Some(PageType::Data(datapage));
+            Some(PageType::Data(datapage))
         }
     }
 
@@ -570,402 +624,419 @@ impl PageManager {
                         return Some(row.data);
                     }
                 }
-                return None;
-            }
-            Some(PageType::Index(node)) => {
-                let ids: Vec<usize> = node
-                    .indexrecord
-                    .row
-                    .iter()
-                    .filter(|row| row.id != 0)
-                    .map(|row| row.id)
-                    .collect();
-                println!("{:?} {}", ids, id);
-                let pos = ids.binary_search(&id).unwrap_or_else(|x| x);
-                let page_id = node.indexrecord.row[pos].pos;
-                return Self::select_recursive(self, page_id, id);
+                None
             }
+            Some(PageType::Index(node)) => Self::select_recursive(self, node.child(id), id),
             None => None,
         }
     }
 
-    pub fn insert(&mut self, id: usize, data: Vec<u8>) {
-        let mut page_id: usize = self.root_page_id;
+    // 从根页开始查找
+    pub fn select(&self, id: usize) -> Option<Vec<u8>> {
+        self.select_recursive(self.root_page_id, id)
+    }
 
-        // TODO 判断是否为空
-        match Self::get_page(&self, page_id as u64) {
-            Some(PageType::Data(mut node)) => {
-                // ! 直接插入一条 datarecord
-                node.push(id, data);
-                if node.pageheader.page_last_insert <= (page_id + 1) * PAGE_SIZE {
-                    let s = node.to_vec_u8();
-                    println!("{:#?}", node);
-                    self.f.write_at(
-                        &s,
-                        node.fileheader.file_page_offset as u64 * PAGE_SIZE as u64,
-                    );
-                } else {
-                    // ! 分裂页
-                    Self::split_page(self, PageType::Data(node));
+    // 按 id 从小到大遍历所有记录，f 返回 false 时停止
+    pub fn for_each<F: FnMut(usize, &[u8]) -> bool>(&self, f: F) {
+        self.range(0, usize::MAX, f);
+    }
+
+    // 只遍历 low <= id <= high 的记录，不会读取范围之外的子树
+    pub fn range<F: FnMut(usize, &[u8]) -> bool>(&self, low: usize, high: usize, mut f: F) {
+        if low <= high {
+            self.walk(self.root_page_id, low, high, &mut f);
+        }
+    }
+
+    // 返回 false 表示遍历已经结束
+    fn walk(
+        &self,
+        page_id: usize,
+        low: usize,
+        high: usize,
+        f: &mut dyn FnMut(usize, &[u8]) -> bool,
+    ) -> bool {
+        match self.get_page(page_id as u64) {
+            Some(PageType::Data(node)) => {
+                for row in node.datarecord.row.iter() {
+                    if row.id < low {
+                        continue;
+                    }
+                    if row.id > high || !f(row.id, &row.data) {
+                        return false;
+                    }
                 }
+                true
             }
             Some(PageType::Index(node)) => {
-                // TODO Find insert node
-                let mut vec = vec![];
-                let mut page_id = self.root_page_id;
-                while let Some(PageType::Index(node)) = Self::get_page(&self, page_id as u64) {
-                    let ids: Vec<usize> = node.indexrecord.row.iter().map(|row| row.id).collect();
-                    let pos = ids.binary_search(&id).unwrap_or_else(|x| x);
-                    vec.push(page_id);
-                    page_id = node.indexrecord.row[pos - 1].pos;
+                let rows = &node.indexrecord.row;
+                for (i, row) in rows.iter().enumerate() {
+                    // 第 i 个子页的 id 都不大于第 i + 1 项的 id，第 0 项视为负无穷；
+                    // 索引中 id 可以重复，分裂后相同的 id 可能分在相邻的两个子页中
+                    if i > 0 && row.id > high {
+                        return false;
+                    }
+                    if rows.get(i + 1).is_some_and(|next| next.id < low) {
+                        continue;
+                    }
+                    if !self.walk(row.pos, low, high, f) {
+                        return false;
+                    }
                 }
-                if let Some(PageType::Data(mut node)) = Self::get_page(&self, page_id as u64) {
+                true
+            }
+            None => true,
+        }
+    }
+
+    pub fn scan(&self) -> Vec<(usize, Vec<u8>)> {
+        let mut rows = vec![];
+        self.for_each(|id, data| {
+            rows.push((id, data.to_vec()));
+            true
+        });
+        rows
+    }
+
+    // 最大的 id，从最右边的子页开始找，跳过删空了的页
+    pub fn max_id(&self) -> Option<usize> {
+        self.max_id_recursive(self.root_page_id)
+    }
+
+    fn max_id_recursive(&self, page_id: usize) -> Option<usize> {
+        match self.get_page(page_id as u64) {
+            Some(PageType::Data(node)) => node.datarecord.row.last().map(|row| row.id),
+            Some(PageType::Index(node)) => node
+                .indexrecord
+                .row
+                .iter()
+                .rev()
+                .find_map(|row| self.max_id_recursive(row.pos)),
+            None => None,
+        }
+    }
+
+    pub fn insert(&mut self, id: usize, data: Vec<u8>) {
+        assert!(data.len() <= MAX_ROW_SIZE, "row is too large for a page");
+        // 记录从根页到叶页经过的索引页
+        let mut path: Vec<IndexPage> = vec![];
+        let mut page_id: usize = self.root_page_id;
+        loop {
+            match self.get_page(page_id as u64) {
+                Some(PageType::Index(node)) => {
+                    page_id = node.child(id);
+                    path.push(node);
+                }
+                Some(PageType::Data(mut node)) => {
+                    // ! 直接插入一条 datarecord
                     node.push(id, data);
-                    if node.pageheader.page_last_insert <= (page_id + 1) * PAGE_SIZE as usize {
-                        let s = node.to_vec_u8();
-                        println!("{:#?}", node);
-                        self.f.write_at(
-                            &s,
-                            node.fileheader.file_page_offset as u64 * PAGE_SIZE as u64,
-                        );
-                    } else {
-                        // ! 分裂页
-                        match Self::split_page(self, PageType::Data(node)) {
-                            Some(PageType::Index(tmp_node)) => {
-                                let mut tmp_node = tmp_node;
-                                while let Some(PageType::Index(index_node)) =
-                                    Self::split_page(self, PageType::Index(tmp_node.clone()))
-                                {
-                                    tmp_node = index_node;
-                                }
-                            }
-                            _ => {}
-                        }
+                    if !node.is_full() {
+                        self.write_data_page(&node);
+                        return;
                     }
+                    // ! 分裂页
+                    let new_rows = self.split_data_page(node);
+                    self.insert_into_parents(path, new_rows);
+                    return;
+                }
+                None => {
+                    // 空表，创建第一个页，它同时也是根页
+                    let mut datapage = DataPage {
+                        fileheader: FileHeader::new(0, 1),
+                        pageheader: PageHeader::new(HEADER_SIZE, 0, HEADER_SIZE, 0, 0),
+                        datarecord: DataRecord { row: vec![] },
+                    };
+                    datapage.push(id, data);
+                    self.write_data_page(&datapage);
+                    self.root_page_id = 0;
+                    self.max_page_id = 0;
+                    return;
                 }
             }
-            None => {
-                let next = HEADER_SIZE + 24 + data.len();
-                let datapage = DataPage {
-                    fileheader: FileHeader::new(0, 1),
-                    pageheader: PageHeader::new(HEADER_SIZE, 1, next, 0, 0),
-                    datarecord: DataRecord {
-                        row: vec![RowData {
-                            next: next,
-                            id,
-                            data: data,
-                        }],
-                    },
-                };
-                let s = datapage.to_vec_u8();
-                let obh = self.f.write_at(&s, 0).unwrap();
-                self.root_page_id = 0;
-                self.max_page_id = 0;
-                return;
+        }
+    }
+
+    // 删除一条记录，返回被删除的数据；页删空后保留，不做合并
+    pub fn delete(&mut self, id: usize) -> Option<Vec<u8>> {
+        let mut page_id = self.root_page_id;
+        loop {
+            match self.get_page(page_id as u64) {
+                Some(PageType::Index(node)) => page_id = node.child(id),
+                Some(PageType::Data(mut node)) => {
+                    let pos = node.datarecord.row.iter().position(|row| row.id == id)?;
+                    let row = node.datarecord.row.remove(pos);
+                    node.relayout();
+                    self.write_data_page(&node);
+                    return Some(row.data);
+                }
+                None => return None,
             }
         }
     }
 
-    fn split_page(&mut self, node: PageType) -> Option<PageType> {
-        match node {
-            PageType::Data(mut node) => {
-                // ! 初始化
-                let is_new_root = if node.fileheader.file_page_offset == self.root_page_id {
-                    true
-                } else {
-                    false
-                };
-                let mut right = node.clone();
-                let len = node.datarecord.row.len();
-                let max_page_id = self.max_page_id;
-                // ! 左节点
-                node.datarecord.row = node.datarecord.row[0..(len / 2)].to_vec();
-                let mut start_row_offset = node.pageheader.page_heap_top;
-                node.datarecord
-                    .row
-                    .iter_mut()
-                    .enumerate()
-                    .for_each(|(i, row)| {
-                        start_row_offset += size_of::<usize>() * 2 + row.data.len();
-                        row.next = start_row_offset;
-                    });
-                node.pageheader.page_last_insert = start_row_offset;
-                node.pageheader.page_n_heap = len / 2;
-                let s_left_offset = node.fileheader.file_page_offset as u64 * PAGE_SIZE as u64;
-                // ! 右节点
-                let start_page_offset = (max_page_id + 1) * PAGE_SIZE;
-                let mut start_row_offset = start_page_offset + HEADER_SIZE;
-                right.datarecord.row = right.datarecord.row[(len / 2)..].to_vec();
-                right
+    // 删除 id 相同并且数据满足 pred 的第一条记录，用于 id 可以重复的索引
+    pub fn delete_where<F: Fn(&[u8]) -> bool>(&mut self, id: usize, pred: F) -> bool {
+        match self.find_row(self.root_page_id, id, &pred) {
+            Some((mut node, pos)) => {
+                node.datarecord.row.remove(pos);
+                node.relayout();
+                self.write_data_page(&node);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // 和 walk 一样只查找可能包含 id 的子页
+    fn find_row(
+        &self,
+        page_id: usize,
+        id: usize,
+        pred: &dyn Fn(&[u8]) -> bool,
+    ) -> Option<(DataPage, usize)> {
+        match self.get_page(page_id as u64) {
+            Some(PageType::Data(node)) => {
+                let pos = node
                     .datarecord
                     .row
-                    .iter_mut()
-                    .enumerate()
-                    .for_each(|(i, row)| {
-                        row.next = start_row_offset + size_of::<usize>() * 2 + row.data.len();
-                        start_row_offset += size_of::<usize>() * 2 + row.data.len();
-                    });
-                let right_n = {
-                    if len % 2 == 1 {
-                        len / 2 + 1
-                    } else {
-                        len / 2
+                    .iter()
+                    .position(|row| row.id == id && pred(&row.data))?;
+                Some((node, pos))
+            }
+            Some(PageType::Index(node)) => {
+                let rows = &node.indexrecord.row;
+                for (i, row) in rows.iter().enumerate() {
+                    if i > 0 && row.id > id {
+                        break;
                     }
-                };
-                right.fileheader = FileHeader::new(max_page_id + 1, max_page_id + 2);
-                right.pageheader = PageHeader::new(
-                    start_page_offset + HEADER_SIZE,
-                    right_n,
-                    start_row_offset,
-                    node.pageheader.page_leval,
-                    node.pageheader.page_index_id,
-                );
-                let s_right_offset = start_page_offset as u64;
-                // ! 判断是否为根页进行分离
-                if is_new_root {
-                    // TODO 自己是根节点，需要创建新的根节点
-                    let start_page_offset = (max_page_id + 2) * PAGE_SIZE;
-                    let indexpage = IndexPage {
-                        fileheader: FileHeader::new(max_page_id + 2, max_page_id + 3),
-                        pageheader: PageHeader::new(
-                            start_page_offset + HEADER_SIZE,
-                            2,
-                            start_page_offset + HEADER_SIZE + 24 * 2,
-                            1,
-                            max_page_id + 2,
-                        ),
-                        indexrecord: IndexRecord {
-                            row: vec![
-                                RowIndex {
-                                    next: start_page_offset + HEADER_SIZE + 24,
-                                    id: 0,
-                                    pos: node.fileheader.file_page_offset,
-                                },
-                                RowIndex {
-                                    next: start_page_offset + HEADER_SIZE + 24 * 2,
-                                    id: right.datarecord.row[0].id,
-                                    pos: right.fileheader.file_page_offset,
-                                },
-                            ],
-                        },
-                    };
-                    let s_top = indexpage.to_vec_u8();
-                    let obh = self
-                        .f
-                        .write_at(
-                            &s_top,
-                            indexpage.fileheader.file_page_offset as u64 * PAGE_SIZE as u64,
-                        )
-                        .unwrap();
-
-                    node.pageheader.page_index_id = indexpage.fileheader.file_page_offset;
-                    right.pageheader.page_index_id = indexpage.fileheader.file_page_offset;
-                    let s_left = node.to_vec_u8();
-                    self.f.write_at(&s_left, s_left_offset);
-                    let s_right = right.to_vec_u8();
-                    self.f.write_at(&s_right, s_right_offset);
-
-                    self.root_page_id = indexpage.fileheader.file_page_offset;
-                    self.max_page_id += 2;
-
-                    return None;
-                } else {
-                    let s_left = node.to_vec_u8();
-                    self.f.write_at(&s_left, s_left_offset);
-                    let s_right = right.to_vec_u8();
-                    self.f.write_at(&s_right, s_right_offset);
-                    self.max_page_id += 1;
-
-                    if let Some(PageType::Index(mut new_top)) =
-                        Self::get_page(&self, node.pageheader.page_index_id as u64)
-                    {
-                        let mut start_row_offset =
-                            new_top.fileheader.file_page_offset * PAGE_SIZE + HEADER_SIZE;
-                        // ! 获取中间值作为 id
-                        let id = right.datarecord.row[0].id;
-                        new_top.indexrecord.row.push(RowIndex {
-                            next: new_top.pageheader.page_last_insert + 8 * 3,
-                            id,
-                            pos: max_page_id + 1,
-                        });
-                        new_top.indexrecord.row.sort_by_key(|row| row.id);
-                        new_top
-                            .indexrecord
-                            .row
-                            .iter_mut()
-                            .enumerate()
-                            .for_each(|(i, row)| {
-                                start_row_offset += size_of::<usize>() * 3;
-                                row.next = start_row_offset;
-                            });
-                        new_top.pageheader.page_n_heap += 1;
-                        new_top.pageheader.page_last_insert = start_row_offset;
-                        let s = new_top.to_vec_u8();
-                        println!("{:#?}", new_top);
-
-                        // TODO 向上递归，是否是新的根爷，重置左右节点的父节点
-                        if new_top.pageheader.page_last_insert
-                            <= (new_top.fileheader.file_page_offset + 1) * PAGE_SIZE
-                        {
-                            self.f.write_at(
-                                &s,
-                                new_top.fileheader.file_page_offset as u64 * PAGE_SIZE as u64,
-                            );
-                        } else {
-                            return Some(PageType::Index(new_top));
-                        }
+                    if rows.get(i + 1).is_some_and(|next| next.id < id) {
+                        continue;
+                    }
+                    if let Some(found) = self.find_row(row.pos, id, pred) {
+                        return Some(found);
                     }
-                    None
                 }
+                None
             }
-            PageType::Index(mut node) => {
-                let is_new_root = if node.pageheader.page_index_id == self.root_page_id {
-                    true
-                } else {
-                    false
-                };
-                let mut right = node.clone();
-                let max_page_id = self.max_page_id;
-                let len = node.pageheader.page_n_heap;
-                // ! 左节点
-                let mut start_row_offset = node.pageheader.page_heap_top;
-                node.indexrecord.row = node.indexrecord.row[0..(len / 2)].to_vec();
-                node.indexrecord
-                    .row
-                    .iter_mut()
-                    .enumerate()
-                    .for_each(|(i, row)| {
-                        start_row_offset += size_of::<usize>() * 3;
-                        row.next = start_row_offset;
-                    });
-                node.pageheader.page_n_heap = len / 2;
-                node.pageheader.page_last_insert = start_row_offset;
-                let s_left_offset = node.fileheader.file_page_offset as u64 * PAGE_SIZE as u64;
-                // ! 右节点
-                let start_page_offset = (max_page_id + 1) * PAGE_SIZE;
-                let mut start_row_offset = start_page_offset + HEADER_SIZE;
-                right.indexrecord.row = right.indexrecord.row[(len / 2)..].to_vec();
-                right
-                    .indexrecord
-                    .row
-                    .iter_mut()
-                    .enumerate()
-                    .for_each(|(i, row)| {
-                        row.next = start_row_offset + size_of::<usize>() * 3;
-                        start_row_offset += size_of::<usize>() * 3;
-                    });
-                let right_n = {
-                    if len % 2 == 1 {
-                        len / 2 + 1
-                    } else {
-                        len / 2
-                    }
-                };
-                right.fileheader = FileHeader::new(max_page_id + 1, max_page_id + 2);
-                right.pageheader = PageHeader::new(
-                    start_page_offset + HEADER_SIZE,
-                    right_n,
-                    start_row_offset,
+            None => None,
+        }
+    }
+
+    pub fn update(&mut self, id: usize, data: Vec<u8>) {
+        self.delete(id);
+        self.insert(id, data);
+    }
+
+    fn write_data_page(&self, node: &DataPage) {
+        let s = node.to_vec_u8();
+        self.f
+            .write_at(&s, (node.fileheader.file_page_offset * PAGE_SIZE) as u64)
+            .unwrap();
+    }
+
+    fn write_index_page(&self, node: &IndexPage) {
+        let s = node.to_vec_u8();
+        self.f
+            .write_at(&s, (node.fileheader.file_page_offset * PAGE_SIZE) as u64)
+            .unwrap();
+    }
+
+    fn set_father(&self, page_id: usize, father: usize) {
+        self.f
+            .write_at(
+                &father.to_ne_bytes(),
+                (page_id * PAGE_SIZE + PAGE_INDEX_ID_OFFSET) as u64,
+            )
+            .unwrap();
+    }
+
+    fn new_page_id(&mut self) -> usize {
+        if let Some(page_id) = self.free_pages.pop() {
+            return page_id;
+        }
+        self.max_page_id += 1;
+        self.max_page_id
+    }
+
+    // 溢出页的页头：page_heap_top 是链表中下一个页（0 表示结束），page_n_heap 是页中的字节数
+    fn write_overflow_page(&self, page_id: usize, level: usize, next: usize, data: &[u8]) {
+        let mut s = vec![];
+        for i in [page_id, page_id + 1, next, data.len(), 0, level, NO_FATHER] {
+            s.extend(i.to_ne_bytes());
+        }
+        s.extend(data);
+        self.f.write_at(&s, (page_id * PAGE_SIZE) as u64).unwrap();
+    }
+
+    // 把放不进一条记录的值写入链接起来的溢出页，返回第一个页的 page_id
+    // 空表先写入空的根页，0 号页总是 B+ 树的页，所以 0 可以表示没有溢出页
+    pub fn write_overflow(&mut self, data: &[u8]) -> usize {
+        if Self::read_header(&self.f, self.root_page_id).is_none() {
+            let root = DataPage {
+                fileheader: FileHeader::new(0, 1),
+                pageheader: PageHeader::new(HEADER_SIZE, 0, HEADER_SIZE, 0, 0),
+                datarecord: DataRecord { row: vec![] },
+            };
+            self.write_data_page(&root);
+            self.root_page_id = 0;
+        }
+        let chunks: Vec<&[u8]> = data.chunks(OVERFLOW_CAPACITY).collect();
+        let page_ids: Vec<usize> = chunks.iter().map(|_| self.new_page_id()).collect();
+        for (i, chunk) in chunks.iter().enumerate() {
+            let next = page_ids.get(i + 1).copied().unwrap_or(0);
+            self.write_overflow_page(page_ids[i], OVERFLOW_PAGE, next, chunk);
+        }
+        page_ids.first().copied().unwrap_or(0)
+    }
+
+    // 从 page_id 开始沿着链表读出 len 个字节
+    pub fn read_overflow(&self, mut page_id: usize, len: usize) -> Vec<u8> {
+        let mut data = Vec::with_capacity(len);
+        while page_id != 0 && data.len() < len {
+            let header = match Self::read_header(&self.f, page_id) {
+                Some(header) if header[5] == OVERFLOW_PAGE => header,
+                _ => break,
+            };
+            let mut buf = vec![0; header[3].min(OVERFLOW_CAPACITY)];
+            self.f
+                .read_exact_at(&mut buf, (page_id * PAGE_SIZE + HEADER_SIZE) as u64)
+                .unwrap();
+            data.extend(buf);
+            page_id = header[2];
+        }
+        data.truncate(len);
+        data
+    }
+
+    // 释放整个链表，释放的页标记为空闲，重新打开文件时也能找到
+    pub fn free_overflow(&mut self, mut page_id: usize) {
+        while page_id != 0 {
+            let header = match Self::read_header(&self.f, page_id) {
+                Some(header) if header[5] == OVERFLOW_PAGE => header,
+                _ => break,
+            };
+            self.write_overflow_page(page_id, FREE_PAGE, 0, &[]);
+            self.free_pages.push(page_id);
+            page_id = header[2];
+        }
+    }
+
+    // 把记录依次装进页里，装不下就放到新的页，
+    // 原来的页保留前面的记录，返回新页的 (最小 id, page_id)
+    fn split_data_page(&mut self, mut node: DataPage) -> Vec<(usize, usize)> {
+        let capacity = PAGE_SIZE - HEADER_SIZE;
+        let mut chunks: Vec<Vec<RowData>> = vec![vec![]];
+        let mut used = 0;
+        for row in node.datarecord.row.drain(..) {
+            let len = size_of::<usize>() * 2 + row.data.len();
+            if used + len > capacity && !chunks.last().unwrap().is_empty() {
+                chunks.push(vec![]);
+                used = 0;
+            }
+            used += len;
+            chunks.last_mut().unwrap().push(row);
+        }
+        let mut chunks = chunks.into_iter();
+        node.datarecord.row = chunks.next().unwrap();
+        node.relayout();
+        self.write_data_page(&node);
+
+        let mut new_rows = vec![];
+        for rows in chunks {
+            let page_id = self.new_page_id();
+            let mut right = DataPage {
+                fileheader: FileHeader::new(page_id, page_id + 1),
+                pageheader: PageHeader::new(0, 0, 0, 0, node.pageheader.page_index_id),
+                datarecord: DataRecord { row: rows },
+            };
+            right.relayout();
+            self.write_data_page(&right);
+            new_rows.push((right.datarecord.row[0].id, page_id));
+        }
+        new_rows
+    }
+
+    // 索引页的分裂，和数据页相同
+    fn split_index_page(&mut self, mut node: IndexPage) -> Vec<(usize, usize)> {
+        let per_page = (PAGE_SIZE - HEADER_SIZE) / (size_of::<usize>() * 3);
+        let mut rows = node.indexrecord.row.split_off(0).into_iter();
+        node.indexrecord.row = rows.by_ref().take(per_page).collect();
+        node.relayout();
+        self.write_index_page(&node);
+
+        let mut new_rows = vec![];
+        let rows: Vec<RowIndex> = rows.collect();
+        for chunk in rows.chunks(per_page) {
+            let page_id = self.new_page_id();
+            let mut right = IndexPage {
+                fileheader: FileHeader::new(page_id, page_id + 1),
+                pageheader: PageHeader::new(
+                    0,
+                    0,
+                    0,
                     node.pageheader.page_leval,
                     node.pageheader.page_index_id,
-                );
-                let s_right_offset = start_page_offset as u64;
-                // TODO 判断是否需要新的根页
-                if is_new_root {
-                    // TODO 自己是根节点，需要创建新的根节点
-                    let start_page_offset = (max_page_id + 2) * PAGE_SIZE;
-                    let indexpage = IndexPage {
-                        fileheader: FileHeader::new(max_page_id + 2, max_page_id + 3),
-                        pageheader: PageHeader::new(
-                            start_page_offset + HEADER_SIZE,
-                            2,
-                            start_page_offset + HEADER_SIZE + 24 * 2,
-                            1,
-                            max_page_id + 2,
-                        ),
-                        indexrecord: IndexRecord {
-                            row: vec![
-                                RowIndex {
-                                    next: start_page_offset + HEADER_SIZE + 24,
-                                    id: 0,
-                                    pos: node.fileheader.file_page_offset,
-                                },
-                                RowIndex {
-                                    next: start_page_offset + HEADER_SIZE + 24 * 2,
-                                    id: right.indexrecord.row[0].id,
-                                    pos: right.fileheader.file_page_offset,
-                                },
-                            ],
-                        },
-                    };
-                    let s_top = indexpage.to_vec_u8();
-                    let obh = self
-                        .f
-                        .write_at(
-                            &s_top,
-                            indexpage.fileheader.file_page_offset as u64 * PAGE_SIZE as u64,
-                        )
-                        .unwrap();
+                ),
+                indexrecord: IndexRecord {
+                    row: chunk.to_vec(),
+                },
+            };
+            right.relayout();
+            self.write_index_page(&right);
+            for row in right.indexrecord.row.iter() {
+                self.set_father(row.pos, page_id);
+            }
+            new_rows.push((right.indexrecord.row[0].id, page_id));
+        }
+        new_rows
+    }
 
-                    node.pageheader.page_index_id = indexpage.fileheader.file_page_offset;
-                    right.pageheader.page_index_id = indexpage.fileheader.file_page_offset;
-                    let s_left = node.to_vec_u8();
-                    self.f.write_at(&s_left, s_left_offset);
-                    let s_right = right.to_vec_u8();
-                    self.f.write_at(&s_right, s_right_offset);
-
-                    self.root_page_id = indexpage.fileheader.file_page_offset;
-                    self.max_page_id += 2;
-
-                    return None;
-                } else {
-                    let s_left = node.to_vec_u8();
-                    self.f.write_at(&s_left, s_left_offset);
-                    let s_right = right.to_vec_u8();
-                    self.f.write_at(&s_right, s_right_offset);
-                    self.max_page_id += 1;
-
-                    let id = right.indexrecord.row[0].id;
-                    if let Some(PageType::Index(mut node)) =
-                        Self::get_page(&self, node.pageheader.page_index_id as u64)
-                    {
-                        let mut start_row_offset =
-                            node.fileheader.file_page_offset * PAGE_SIZE + HEADER_SIZE;
-                        node.indexrecord.row.push(RowIndex {
-                            next: node.pageheader.page_last_insert + 8 * 3,
-                            id,
-                            pos: self.max_page_id + 1,
-                        });
-                        node.indexrecord.row.sort_by_key(|row| row.id);
-                        node.indexrecord
-                            .row
-                            .iter_mut()
-                            .enumerate()
-                            .for_each(|(i, row)| {
-                                start_row_offset += size_of::<usize>() * 3;
-                                row.next = start_row_offset;
-                            });
-                        node.pageheader.page_n_heap += 1;
-
-                        let s = node.to_vec_u8();
-                        println!("{:#?}", node);
-
-                        // TODO 是否需要像上递归
-                        if len + node.pageheader.page_last_insert
-                            <= (node.fileheader.file_page_offset + 1) * PAGE_SIZE
-                        {
-                            self.f.write_at(
-                                &s,
-                                node.fileheader.file_page_offset as u64 * PAGE_SIZE as u64,
-                            );
-                        } else {
-                            return Some(PageType::Index(node));
-                        }
-                    }
-                }
-                None
+    // 把分裂出来的新页登记到父页中，父页放不下时继续向上分裂
+    fn insert_into_parents(&mut self, mut path: Vec<IndexPage>, mut new_rows: Vec<(usize, usize)>) {
+        while let Some(mut father) = path.pop() {
+            let father_id = father.fileheader.file_page_offset;
+            for &(id, pos) in new_rows.iter() {
+                self.set_father(pos, father_id);
+                // 放在 id 相同的索引后面，保证新页在原来的页右边
+                let at = father.indexrecord.row.partition_point(|row| row.id <= id);
+                father
+                    .indexrecord
+                    .row
+                    .insert(at.max(1), RowIndex { next: 0, id, pos });
             }
+            father.relayout();
+            if !father.is_full() {
+                self.write_index_page(&father);
+                return;
+            }
+            new_rows = self.split_index_page(father);
+        }
+        // 根页分裂了，需要创建新的根页
+        let old_root = self.root_page_id;
+        let level = match self.get_page(old_root as u64) {
+            Some(PageType::Index(node)) => node.pageheader.page_leval,
+            _ => 0,
+        };
+        let root_id = self.new_page_id();
+        let mut row = vec![RowIndex {
+            next: 0,
+            id: 0,
+            pos: old_root,
+        }];
+        for (id, pos) in new_rows {
+            row.push(RowIndex { next: 0, id, pos });
         }
+        let mut root = IndexPage {
+            fileheader: FileHeader::new(root_id, root_id + 1),
+            pageheader: PageHeader::new(0, 0, 0, level + 1, root_id),
+            indexrecord: IndexRecord { row },
+        };
+        root.relayout();
+        self.write_index_page(&root);
+        for row in root.indexrecord.row.iter() {
+            self.set_father(row.pos, root_id);
+        }
+        self.root_page_id = root_id;
     }
 
     fn append_data(&self, node: PageType, page_id: usize, id: usize, data: &str) -> bool {
@@ -1116,6 +1187,124 @@ mod test {
         println!("The first line was {} bytes long", after - before);
     }
 
+    #[test]
+    fn split_and_scan() {
+        let path = std::env::temp_dir().join(format!(
+            "rs_sql_page_split_and_scan_{}.db",
+            std::process::id()
+        ));
+        let mut page_manager = PageManager::create(path.to_str().unwrap());
+        // 打乱插入顺序，让各层的页都发生分裂
+        let mut ids: Vec<usize> = (1..=120).collect();
+        for i in 0..ids.len() {
+            ids.swap(i, (i * 7919 + 13) % 120);
+        }
+        for &id in ids.iter() {
+            page_manager.insert(id, "x".repeat(id % 40).into_bytes());
+        }
+        // 重新打开文件，根页需要能被找到
+        let mut page_manager = PageManager::read_file(path.to_str().unwrap());
+        assert_ne!(page_manager.root_page_id, 0);
+        for id in 1..=120 {
+            assert_eq!(
+                page_manager.select(id),
+                Some("x".repeat(id % 40).into_bytes())
+            );
+        }
+        let scanned: Vec<usize> = page_manager.scan().iter().map(|(id, _)| *id).collect();
+        assert_eq!(scanned, (1..=120).collect::<Vec<usize>>());
+        assert_eq!(page_manager.max_id(), Some(120));
+        let mut ranged = vec![];
+        page_manager.range(37, 81, |id, _| {
+            ranged.push(id);
+            true
+        });
+        assert_eq!(ranged, (37..=81).collect::<Vec<usize>>());
+        let mut ranged = vec![];
+        page_manager.range(115, 500, |id, _| {
+            ranged.push(id);
+            true
+        });
+        assert_eq!(ranged, (115..=120).collect::<Vec<usize>>());
+        page_manager.range(50, 40, |_, _| panic!("empty range"));
+
+        assert_eq!(page_manager.delete(120), Some(vec![]));
+        assert_eq!(page_manager.delete(120), None);
+        page_manager.update(7, b"new".to_vec());
+        assert_eq!(page_manager.select(7), Some(b"new".to_vec()));
+        assert_eq!(page_manager.max_id(), Some(119));
+        assert_eq!(page_manager.scan().len(), 119);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn duplicate_ids() {
+        let path = std::env::temp_dir().join(format!(
+            "rs_sql_page_duplicate_ids_{}.db",
+            std::process::id()
+        ));
+        let mut page_manager = PageManager::create(path.to_str().unwrap());
+        // 每个 id 有 60 条记录，相同 id 的记录会分到多个页中
+        for n in 0..60usize {
+            for id in [5, 3, 9] {
+                let mut data = n.to_ne_bytes().to_vec();
+                data.resize(16, 0);
+                page_manager.insert(id, data);
+            }
+        }
+        let count = |page_manager: &PageManager, id| {
+            let mut count = 0;
+            page_manager.range(id, id, |_, _| {
+                count += 1;
+                true
+            });
+            count
+        };
+        assert_eq!(count(&page_manager, 5), 60);
+        assert_eq!(page_manager.scan().len(), 180);
+        for n in 0..60usize {
+            assert!(page_manager.delete_where(5, |data| data[..8] == n.to_ne_bytes()));
+        }
+        assert!(!page_manager.delete_where(5, |_| true));
+        assert_eq!(count(&page_manager, 3), 60);
+        assert_eq!(count(&page_manager, 5), 0);
+        assert_eq!(count(&page_manager, 9), 60);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn overflow() {
+        let path =
+            std::env::temp_dir().join(format!("rs_sql_page_overflow_{}.db", std::process::id()));
+        let mut page_manager = PageManager::create(path.to_str().unwrap());
+        let data: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        // 空表先写入根页，溢出页从 1 号页开始
+        let first = page_manager.write_overflow(&data);
+        assert_eq!(first, 1);
+        assert_eq!(page_manager.read_overflow(first, data.len()), data);
+        assert_eq!(page_manager.write_overflow(&[]), 0);
+        // 溢出页不影响 B+ 树的记录
+        for id in 1..=40 {
+            page_manager.insert(id, vec![id as u8; 20]);
+        }
+        let mut page_manager = PageManager::read_file(path.to_str().unwrap());
+        assert_eq!(page_manager.scan().len(), 40);
+        assert_eq!(page_manager.select(40), Some(vec![40; 20]));
+        assert_eq!(page_manager.read_overflow(first, data.len()), data);
+
+        // 释放的页重新打开文件后仍然可以复用
+        let pages = data.len().div_ceil(OVERFLOW_CAPACITY);
+        page_manager.free_overflow(first);
+        let mut page_manager = PageManager::read_file(path.to_str().unwrap());
+        assert_eq!(page_manager.free_pages.len(), pages);
+        let max_page_id = page_manager.max_page_id;
+        let second = page_manager.write_overflow(&data[..500]);
+        assert_eq!(page_manager.max_page_id, max_page_id);
+        assert_eq!(page_manager.read_overflow(second, 500), data[..500]);
+        assert_eq!(page_manager.scan().len(), 40);
+        let _ = std::fs::remove_file(&path);
+    }
+
     #[test]
     fn a() {
         use std::mem::size_of;
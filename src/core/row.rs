@@ -1,8 +1,15 @@
-use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+use std::mem::size_of;
 
 use bit::BitIndex;
 
-use crate::parser::parser::{Field, Parser};
+use super::decimal::Decimal;
+use super::error::DbError;
+use super::page::PageManager;
+use super::schema::Schema;
+use super::temporal;
+use super::value::Value;
+use crate::parser::ast::Field;
 
 // create table demo (
 //     col3 int ,
@@ -13,8 +20,9 @@ use crate::parser::parser::{Field, Parser};
 struct Row {
     empty_list_offset: u8, // 空值列表偏移量
     variablelist: Vec<u8>, // 正常值列表
-    emptylist: u8,         // 空值列表
+    emptylist: Vec<u8>,    // 空值列表
     datalist: Vec<u8>,     // 数据列表
+    overflowlist: Vec<u8>, // 还没有写入溢出页的 text 和 blob
 }
 impl Default for Row {
     fn default() -> Self {
@@ -22,6 +30,7 @@ impl Default for Row {
             variablelist: Default::default(),
             emptylist: Default::default(),
             datalist: Default::default(),
+            overflowlist: Default::default(),
             empty_list_offset: Default::default(),
         }
     }
@@ -30,7 +39,16 @@ impl Row {
     pub fn new() -> Self {
         Row::default()
     }
+
+    fn push_overflow(&mut self, mut data: Vec<u8>) {
+        self.datalist.extend(0usize.to_ne_bytes());
+        self.datalist.extend(data.len().to_ne_bytes());
+        self.overflowlist.append(&mut data);
+    }
 }
+// text 和 blob 在数据列表中占 16 个字节：第一个溢出页的 page_id 和值的长度
+const OVERFLOW_SLOT: usize = size_of::<usize>() * 2;
+
 // 功能是把文件中的记录解析为需要的类型，或者是能把新插入的数据按照类型重新编码为最终会被插入到文件里的字节流
 pub struct RowManager {
     fields: Vec<Field>,
@@ -38,162 +56,649 @@ pub struct RowManager {
 impl RowManager {
     pub fn new(frm_name: &str) -> Self {
         //
-        let a = Parser::de(frm_name);
+        let a = Schema::de(frm_name);
         let fields = a.ptable;
-        println!("self.map: {:#?}", &fields);
         Self { fields }
     }
 
-    pub fn from_parser(&mut self, parser: Parser) -> Vec<u8> {
+    // alter table 时按新的表结构重新编码记录
+    pub fn with_fields(fields: Vec<Field>) -> Self {
+        Self { fields }
+    }
+
+    // 按表结构的字段顺序编码插入的值，没有给出的字段为 NULL
+    // 值的类型和字段类型不匹配时返回错误，不会写入任何数据
+    pub fn from_parser(&self, pfields: &[String], pvalues: Vec<Value>) -> Result<Vec<u8>, DbError> {
+        for k in pfields.iter() {
+            if !self.fields.iter().any(|field| &field.value == k) {
+                return Err(DbError::NoSuchColumn(k.clone()));
+            }
+        }
+        // 给出了字段列表时值的个数必须一致，否则不能多于表的字段数
+        let expected = if pfields.is_empty() {
+            self.fields.len()
+        } else {
+            pfields.len()
+        };
+        if pvalues.len() > expected || (!pfields.is_empty() && pvalues.len() < expected) {
+            return Err(DbError::ValueCount {
+                expected,
+                found: pvalues.len(),
+            });
+        }
         let mut row = Row::new();
-        let mut empty_list_len = 0;
-        for (i, (k, v)) in parser.pfields.iter().zip(parser.pvalues).enumerate() {
-            let field = &self.fields[i];
+        row.emptylist = vec![0; Self::empty_list_len(self.fields.len())];
+        for (i, field) in self.fields.iter().enumerate() {
+            let v = if pfields.is_empty() {
+                pvalues.get(i)
+            } else {
+                pfields
+                    .iter()
+                    .position(|k| k == &field.value)
+                    .and_then(|j| pvalues.get(j))
+            };
+            let v = v.cloned().unwrap_or(Value::Null);
             // 如果字段属于变长字段类型就要在变长字段列表里标记长度，
             // 如果变长字段为空
 
             //   变长字段 --> 标记长度
             //      |
-            //      | 字符串为空
+            //      | 值为 NULL
             //      v
             //   标记为空
 
             //   普通字段 --> 不标记长度
             //       |
-            //       | 字符串为空
+            //       | 值为 NULL
             //       v
             //    标记为空
-            if v.len() == 0 {
-                row.emptylist.set_bit(i, true);
-            } else {
-                println!("{}", field.fieldtype.as_str());
-                match field.fieldtype.as_str() {
-                    "int" => {
-                        let num: u64 = v.parse().unwrap();
-                        println!("num: {} ", &num);
-                        let b = num.to_ne_bytes();
-                        row.datalist.append(&mut b.to_vec());
+            match (field.fieldtype.as_str(), v) {
+                (_, Value::Null) => {
+                    if !field.can_null {
+                        return Err(DbError::NotNull(field.value.clone()));
+                    }
+                    row.emptylist[i / 8].set_bit(i % 8, true);
+                }
+                // 整数按字段的字节数保存，超出类型的范围时返回错误；
+                // 超出 i64 的整数是小数位数为 0 的 decimal，bigint unsigned 按 u64 保存
+                (_, v) if field.is_integer() && matches!(v, Value::Int(_) | Value::Decimal(_)) => {
+                    let (min, max) = field.range();
+                    let num = match v.as_integer() {
+                        Some(num) if num >= min && num <= max => num,
+                        Some(_) => {
+                            return Err(DbError::OutOfRange {
+                                column: field.value.clone(),
+                                value: v,
+                            })
+                        }
+                        None => {
+                            return Err(DbError::TypeMismatch {
+                                column: field.value.clone(),
+                                fieldtype: field.fieldtype.clone(),
+                                value: v,
+                            })
+                        }
+                    };
+                    match field.bitsize {
+                        1 => row.datalist.extend((num as i8).to_ne_bytes()),
+                        2 => row.datalist.extend((num as i16).to_ne_bytes()),
+                        4 => row.datalist.extend((num as i32).to_ne_bytes()),
+                        _ if field.unsigned => row.datalist.extend((num as u64).to_ne_bytes()),
+                        _ => row.datalist.extend((num as i64).to_ne_bytes()),
+                    }
+                }
+                // 日期和时间可以写成 ISO-8601 字符串，按字段类型转换后保存为定长的整数
+                (fieldtype, v) if temporal::is_temporal(fieldtype) => {
+                    match temporal::convert(fieldtype, &v) {
+                        Some(Value::Date(days)) => row.datalist.extend(days.to_ne_bytes()),
+                        Some(Value::Time(micros)) | Some(Value::DateTime(micros)) => {
+                            row.datalist.extend(micros.to_ne_bytes())
+                        }
+                        _ => {
+                            return Err(DbError::TypeMismatch {
+                                column: field.value.clone(),
+                                fieldtype: field.fieldtype.clone(),
+                                value: v,
+                            })
+                        }
+                    }
+                }
+                // decimal 按字段的小数位数四舍五入后保存为整数，
+                // 总位数不超过 18 时用 8 个字节，否则用 16 个字节
+                ("decimal", v) => {
+                    let decimal = match &v {
+                        Value::Float(n) => Decimal::from_f64(*n),
+                        v => v.as_decimal(),
+                    };
+                    let decimal = match decimal {
+                        Some(decimal) => decimal,
+                        None => {
+                            return Err(DbError::TypeMismatch {
+                                column: field.value.clone(),
+                                fieldtype: field.fieldtype.clone(),
+                                value: v,
+                            })
+                        }
+                    };
+                    match decimal.rescale(field.scale as u32) {
+                        Some(decimal) if decimal.precision() <= field.bitsize => {
+                            if field.bitsize <= 18 {
+                                row.datalist.extend((decimal.value() as i64).to_ne_bytes());
+                            } else {
+                                row.datalist.extend(decimal.value().to_ne_bytes());
+                            }
+                        }
+                        _ => {
+                            return Err(DbError::OutOfRange {
+                                column: field.value.clone(),
+                                value: v,
+                            })
+                        }
                     }
-                    "varchar" => {
-                        // 添加变长字段的长度
-                        empty_list_len += 1;
-                        row.variablelist.push(v.as_bytes().len() as u8);
-                        row.datalist.append(&mut v.as_bytes().to_vec());
-                        println!("v: {} {}", &v, v.as_bytes().len() as u8);
+                }
+                // float 保存为 f32，超出 f32 范围的有限值返回错误；double 保存为 f64
+                // NaN 和无穷大只能通过预处理语句的参数写入
+                ("float" | "double", v) => {
+                    let num = match &v {
+                        Value::Float(n) => *n,
+                        Value::Int(n) => *n as f64,
+                        Value::Decimal(d) => d.to_f64(),
+                        _ => {
+                            return Err(DbError::TypeMismatch {
+                                column: field.value.clone(),
+                                fieldtype: field.fieldtype.clone(),
+                                value: v,
+                            })
+                        }
+                    };
+                    if field.fieldtype == "double" {
+                        row.datalist.extend(num.to_ne_bytes());
+                    } else if num.is_finite() && num.abs() > f32::MAX as f64 {
+                        return Err(DbError::OutOfRange {
+                            column: field.value.clone(),
+                            value: v,
+                        });
+                    } else {
+                        row.datalist.extend((num as f32).to_ne_bytes());
                     }
-                    _ => {
-                        // 添加普通字段
-                        row.datalist.append(&mut v.as_bytes().to_vec());
-                        println!("v: {} {}", &v, v.as_bytes().len() as u8);
+                }
+                // boolean 占一个字节，也可以写成 0 和 1
+                ("boolean", Value::Bool(b)) => row.datalist.push(b as u8),
+                ("boolean", Value::Int(n)) if n == 0 || n == 1 => row.datalist.push(n as u8),
+                // 编码时 page_id 为 0，值先放在记录的最后，写入表之前由 spill 移到溢出页
+                ("text", Value::Str(v)) => row.push_overflow(v.into_bytes()),
+                ("blob", Value::Bytes(v)) => row.push_overflow(v),
+                ("blob", Value::Str(v)) => row.push_overflow(v.into_bytes()),
+                ("varchar", Value::Str(v)) => {
+                    if v.len() > field.bitsize {
+                        return Err(DbError::TooLong {
+                            column: field.value.clone(),
+                            max: field.bitsize,
+                        });
                     }
+                    // 添加变长字段的长度
+                    row.variablelist.push(v.len() as u8);
+                    row.datalist.append(&mut v.as_bytes().to_vec());
+                }
+                ("char", Value::Str(v)) => {
+                    if v.len() > field.bitsize {
+                        return Err(DbError::TooLong {
+                            column: field.value.clone(),
+                            max: field.bitsize,
+                        });
+                    }
+                    // 定长字段，不足的部分用空格补齐
+                    let mut bytes = v.as_bytes().to_vec();
+                    bytes.resize(field.bitsize, b' ');
+                    row.datalist.append(&mut bytes);
+                }
+                (_, v) => {
+                    return Err(DbError::TypeMismatch {
+                        column: field.value.clone(),
+                        fieldtype: field.fieldtype.clone(),
+                        value: v,
+                    });
                 }
             }
         }
         row.empty_list_offset = row.variablelist.len() as u8 + 1;
 
         let mut res: Vec<u8> = vec![row.empty_list_offset];
-        println!("{:?}", res);
         res.append(&mut row.variablelist);
-        println!("{:?}", res);
-        res.push(row.emptylist);
-        println!("{:?}", res);
+        res.append(&mut row.emptylist);
         res.append(&mut row.datalist);
+        res.append(&mut row.overflowlist);
+        Ok(res)
+    }
+
+    // 把一条记录解析为按字段顺序排列的值，
+    // 只用于 text 和 blob 还没有写入溢出页的记录，比如刚编码的记录
+    pub fn to_row(&self, data: Vec<u8>) -> Vec<Value> {
+        self.decode(&data, None)
+    }
+
+    // 解析表中的记录，text 和 blob 从溢出页读出
+    pub(crate) fn read(&self, data: Vec<u8>, pages: &PageManager) -> Vec<Value> {
+        self.decode(&data, Some(pages))
+    }
+
+    // 把记录末尾的 text 和 blob 写入溢出页，返回写入表中的记录
+    pub(crate) fn spill(&self, mut data: Vec<u8>, pages: &mut PageManager) -> Vec<u8> {
+        let (slots, end) = self.layout(&data);
+        let mut tail = end;
+        for (field, slot) in self.fields.iter().zip(slots) {
+            let start = match slot {
+                Some((start, _)) if Self::is_overflow(field) => start,
+                _ => continue,
+            };
+            let (page_id, len) = Self::overflow_slot(&data[start..]);
+            if page_id != 0 || len == 0 {
+                continue;
+            }
+            let page_id = pages.write_overflow(&data[tail..tail + len]);
+            tail += len;
+            data[start..start + 8].copy_from_slice(&page_id.to_ne_bytes());
+        }
+        data.truncate(end);
+        data
+    }
+
+    // 删除或者修改记录后释放它的溢出页
+    pub(crate) fn release(&self, data: &[u8], pages: &mut PageManager) {
+        let (slots, _) = self.layout(data);
+        for (field, slot) in self.fields.iter().zip(slots) {
+            if let Some((start, _)) = slot.filter(|_| Self::is_overflow(field)) {
+                let (page_id, _) = Self::overflow_slot(&data[start..]);
+                pages.free_overflow(page_id);
+            }
+        }
+    }
+
+    // 写入表中时记录的长度，不包括之后会移到溢出页的部分
+    pub fn stored_len(&self, data: &[u8]) -> usize {
+        self.layout(data).1
+    }
+
+    fn decode(&self, data: &[u8], pages: Option<&PageManager>) -> Vec<Value> {
+        let (slots, end) = self.layout(data);
+        let mut tail = end;
+        let mut res = vec![];
+        for (field, slot) in self.fields.iter().zip(slots) {
+            let (start, len) = match slot {
+                Some(slot) => slot,
+                None => {
+                    res.push(Value::Null);
+                    continue;
+                }
+            };
+            let bytes = &data[start..start + len];
+            let value = match field.fieldtype.as_str() {
+                "text" | "blob" => {
+                    let (page_id, len) = Self::overflow_slot(bytes);
+                    let bytes = if page_id == 0 {
+                        tail += len;
+                        data[tail - len..tail].to_vec()
+                    } else {
+                        pages
+                            .expect("overflow pages of a stored row")
+                            .read_overflow(page_id, len)
+                    };
+                    if field.fieldtype == "text" {
+                        Value::Str(String::from_utf8_lossy(&bytes).to_string())
+                    } else {
+                        Value::Bytes(bytes)
+                    }
+                }
+                _ => Self::value(field, bytes),
+            };
+            res.push(value);
+        }
         res
     }
 
-    pub fn to_row(&mut self, data: Vec<u8>) -> HashMap<String, Option<String>> {
-        let empty_list_offset = data[0];
+    // 每个字段在记录中的位置和长度，NULL 为 None；
+    // 同时返回数据列表的结尾，之后是还没有写入溢出页的 text 和 blob
+    fn layout(&self, data: &[u8]) -> (Vec<Option<(usize, usize)>>, usize) {
+        let empty_list_offset = data[0] as usize;
+        let empty_list_len = Self::empty_list_len(self.fields.len());
         // 空值列表
-        let emptylist: u8 = data[empty_list_offset as usize].bit_range(0..self.fields.len());
+        let emptylist = &data[empty_list_offset..empty_list_offset + empty_list_len];
         // 变长字段列表
-        let mut variablelist = vec![];
-        for i in 1..empty_list_offset {
-            let length: u8 = data[i as usize];
-            variablelist.push(length);
-        }
-        println!("empty_list.len(): {}", self.fields.len());
-        println!("emptylist: {:#b}", emptylist);
-        println!("variable_list: {:?}", variablelist);
-        let datalist = data[(empty_list_offset + 1) as usize..].to_vec();
-        println!("datalist: {:?} datalist_len: {}", datalist, datalist.len());
-        let mut res: HashMap<String, Option<String>> = HashMap::new();
-        let mut data_start_offset = 0;
+        let mut variablelist = data[1..empty_list_offset].iter();
+        let mut offset = empty_list_offset + empty_list_len;
+        let mut slots = vec![];
         for (i, field) in self.fields.iter().enumerate() {
-            println!(
-                "============================field.value: {}, field.fieldtype: {}",
-                field.value, field.fieldtype
-            );
-            if emptylist.bit(i) == true {
-                res.insert(field.value.clone(), None);
+            if emptylist[i / 8].bit(i % 8) {
+                slots.push(None);
                 continue;
-            } else {
-                // 若不为空，获取到 bitsize 查询到字段并转为字符串
-                print!("start_offset: {} ", data_start_offset);
-                let (bitlen, data): (u8, String) = match field.fieldtype.as_str() {
-                    "int" => {
-                        let bitlen: u8 = 8;
-                        let mut buf: [u8; 8] = [0; 8];
-                        for (i, j) in
-                            (data_start_offset..(data_start_offset + bitlen as usize)).enumerate()
-                        {
-                            buf[i] = datalist[j].clone();
-                        }
+            }
+            // 若不为空，由字段类型得到数据的长度
+            let len = match field.fieldtype.as_str() {
+                _ if field.is_integer() => field.bitsize,
+                "decimal" if field.bitsize <= 18 => 8,
+                "decimal" => 16,
+                "float" | "date" => 4,
+                "double" | "time" | "datetime" | "timestamp" => 8,
+                "boolean" => 1,
+                "text" | "blob" => OVERFLOW_SLOT,
+                "varchar" => *variablelist.next().unwrap() as usize,
+                _ => field.bitsize,
+            };
+            slots.push(Some((offset, len)));
+            offset += len;
+        }
+        (slots, offset)
+    }
 
-                        let data = usize::from_ne_bytes(buf).to_string();
-                        print!("data: {} ", data);
-                        (bitlen, data)
-                    }
-                    "varchar" => {
-                        let bitlen = variablelist.remove(0);
-                        let data: Vec<u8> = datalist
-                            [data_start_offset..(data_start_offset + bitlen as usize)]
-                            .to_vec();
-                        print!("data: {:?} ", data);
-                        let data = String::from_utf8_lossy(&data);
-                        print!("data: {:?} ", data);
-                        (bitlen, data.to_string())
-                    }
-                    _ => {
-                        let bitlen = field.bitsize as u8;
-                        let data: Vec<u8> = datalist[data_start_offset as usize
-                            ..(data_start_offset + bitlen as usize) as usize]
-                            .to_vec();
-                        let data = String::from_utf8_lossy(&data).to_string();
-                        (bitlen, data)
-                    }
-                };
-                print!("bit_size: {} ", &bitlen);
-                data_start_offset += bitlen as usize;
-                println!("{} {} {}", field.value, bitlen, data);
-                res.insert(field.value.clone(), Some(data.to_string()));
+    // 定长字段和 char、varchar 的值
+    fn value(field: &Field, data: &[u8]) -> Value {
+        match field.fieldtype.as_str() {
+            _ if field.is_integer() => Self::integer(data, field.unsigned),
+            "decimal" if data.len() == 8 => {
+                let value = i64::from_ne_bytes(data.try_into().unwrap()) as i128;
+                Value::Decimal(Decimal::new(value, field.scale as u32))
             }
+            "decimal" => {
+                let value = i128::from_ne_bytes(data.try_into().unwrap());
+                Value::Decimal(Decimal::new(value, field.scale as u32))
+            }
+            // f32 按最短的十进制表示转为 f64，写入 0.1 读出的也是 0.1
+            "float" => {
+                let num = f32::from_ne_bytes(data.try_into().unwrap());
+                Value::Float(num.to_string().parse().unwrap())
+            }
+            "double" => Value::Float(f64::from_ne_bytes(data.try_into().unwrap())),
+            "boolean" => Value::Bool(data[0] != 0),
+            "date" => Value::Date(i32::from_ne_bytes(data.try_into().unwrap())),
+            "time" => Value::Time(i64::from_ne_bytes(data.try_into().unwrap())),
+            "datetime" | "timestamp" => {
+                Value::DateTime(i64::from_ne_bytes(data.try_into().unwrap()))
+            }
+            "varchar" => Value::Str(String::from_utf8_lossy(data).to_string()),
+            _ => Value::Str(String::from_utf8_lossy(data).trim_end().to_string()),
         }
-        res
+    }
+
+    fn is_overflow(field: &Field) -> bool {
+        matches!(field.fieldtype.as_str(), "text" | "blob")
+    }
+
+    // 溢出页的 page_id 和值的长度
+    fn overflow_slot(data: &[u8]) -> (usize, usize) {
+        let page_id = usize::from_ne_bytes(data[..8].try_into().unwrap());
+        let len = usize::from_ne_bytes(data[8..16].try_into().unwrap());
+        (page_id, len)
+    }
+
+    pub fn fields(&self) -> &Vec<Field> {
+        &self.fields
+    }
+
+    // 按字节数和有无符号解析整数，bigint unsigned 超出 i64 的值解析为 decimal
+    fn integer(data: &[u8], unsigned: bool) -> Value {
+        let num = match (data.len(), unsigned) {
+            (1, false) => i8::from_ne_bytes([data[0]]) as i128,
+            (1, true) => data[0] as i128,
+            (2, false) => i16::from_ne_bytes([data[0], data[1]]) as i128,
+            (2, true) => u16::from_ne_bytes([data[0], data[1]]) as i128,
+            (4, false) => i32::from_ne_bytes([data[0], data[1], data[2], data[3]]) as i128,
+            (4, true) => u32::from_ne_bytes([data[0], data[1], data[2], data[3]]) as i128,
+            (_, false) => i64::from_ne_bytes(data.try_into().unwrap()) as i128,
+            (_, true) => u64::from_ne_bytes(data.try_into().unwrap()) as i128,
+        };
+        match i64::try_from(num) {
+            Ok(num) => Value::Int(num),
+            Err(_) => Value::Decimal(Decimal::new(num, 0)),
+        }
+    }
+
+    // 每个字段在空值列表中占一位
+    fn empty_list_len(fields: usize) -> usize {
+        fields.div_ceil(8)
     }
 }
 
 #[cfg(test)]
 mod test {
+    use super::super::super::parser::ast::{Field, InsertSource, Statement};
     use super::super::super::parser::parser::Parser;
     use super::super::super::parser::token;
-    use super::RowManager;
+    use super::super::page::PageManager;
+    use super::{DbError, Decimal, RowManager, Value};
+
+    // 解析 insert 语句，得到字段和值
+    fn parse(sql: &str) -> (Vec<String>, Vec<Value>) {
+        match Parser::new(token::tokenize(sql)).parse().unwrap() {
+            Statement::Insert(insert) => {
+                let values = match &insert.source {
+                    InsertSource::Values(rows) => rows[0]
+                        .iter()
+                        .map(|expr| expr.as_literal().unwrap().clone())
+                        .collect(),
+                    InsertSource::Select(_) => panic!("not a values list"),
+                };
+                (insert.columns, values)
+            }
+            statement => panic!("not an insert statement: {:?}", statement),
+        }
+    }
+
+    // 解析 create table 语句，得到字段
+    fn fields(sql: &str) -> Vec<Field> {
+        match Parser::new(token::tokenize(sql)).parse().unwrap() {
+            Statement::CreateTable(create) => create.fields,
+            statement => panic!("not a create table statement: {:?}", statement),
+        }
+    }
 
     #[test]
     fn b() {
-        let sql =
-            "   \ninsert into user(id,col2,col3,col4,name)values(1,4,aaaaa,bbbb, cc)where id=1; ";
-        let token_stream = token::trim_to_token_stream(&token::trim_code(sql));
-        let mut parser: Parser = Parser::new();
-        parser.parse(token_stream);
-        println!("{:?}", parser);
+        let sql = "   \ninsert into user(id,col2,col3,col4,name)values(1,4,'aaaaa','bbbb', 'cc'); ";
+        let (pfields, pvalues) = parse(sql);
+        println!("{:?} {:?}", pfields, pvalues);
 
-        let mut rowmanager: RowManager = RowManager::new("user.frm");
-        let bytes = rowmanager.from_parser(parser);
+        let rowmanager: RowManager = RowManager::new("user.frm");
+        let bytes = rowmanager.from_parser(&pfields, pvalues).unwrap();
         println!("{:?}", bytes);
         let res = rowmanager.to_row(bytes);
         println!("{:#?}", res);
     }
 
+    #[test]
+    fn type_check() {
+        let encode = |rowmanager: &mut RowManager, sql: &str| {
+            let (pfields, pvalues) = parse(sql);
+            rowmanager.from_parser(&pfields, pvalues)
+        };
+        let mut rowmanager: RowManager = RowManager::new("user.frm");
+        let bytes = encode(
+            &mut rowmanager,
+            "insert into user(name,id,col3)values('Bob Li', 7, 'ab')",
+        )
+        .unwrap();
+        let res = rowmanager.to_row(bytes);
+        assert_eq!(
+            res,
+            vec![
+                Value::Int(7),
+                Value::Null,
+                Value::Str("ab".to_string()),
+                Value::Null,
+                Value::Str("Bob Li".to_string()),
+            ]
+        );
+
+        let err = encode(&mut rowmanager, "insert into user(id,name)values('1','a')");
+        assert!(matches!(err, Err(DbError::TypeMismatch { .. })));
+        // user.frm 中的 int 是 8 个字节的有符号整数
+        let bytes = encode(&mut rowmanager, "insert into user(id,name)values(-5,'a')").unwrap();
+        assert_eq!(rowmanager.to_row(bytes)[0], Value::Int(-5));
+        let err = encode(&mut rowmanager, "insert into user(id,name)values(1,null)");
+        assert_eq!(err, Err(DbError::NotNull("name".to_string())));
+        let err = encode(
+            &mut rowmanager,
+            "insert into user(col3,name)values('abcdef','a')",
+        );
+        assert!(matches!(err, Err(DbError::TooLong { .. })));
+        let err = encode(&mut rowmanager, "insert into user(nope)values(1)");
+        assert_eq!(err, Err(DbError::NoSuchColumn("nope".to_string())));
+        let err = encode(&mut rowmanager, "insert into user(id,name)values(1)");
+        assert_eq!(
+            err,
+            Err(DbError::ValueCount {
+                expected: 2,
+                found: 1
+            })
+        );
+    }
+
+    #[test]
+    fn integer() {
+        let rowmanager = RowManager::with_fields(fields(
+            "create table n (a tinyint, b smallint unsigned, c int, d bigint unsigned)",
+        ));
+        let encode = |values: [i64; 4]| {
+            rowmanager.from_parser(&[], values.iter().map(|&v| Value::Int(v)).collect())
+        };
+        for values in [
+            [-128, 65535, i32::MIN as i64, i64::MAX],
+            [127, 0, i32::MAX as i64, 0],
+            [-1, 1, -1, 1],
+        ] {
+            let bytes = encode(values).unwrap();
+            // 1 + 空值列表 1 + 1 + 2 + 4 + 8
+            assert_eq!(bytes.len(), 17);
+            let res: Vec<Value> = values.iter().map(|&v| Value::Int(v)).collect();
+            assert_eq!(rowmanager.to_row(bytes), res);
+        }
+        for values in [
+            [128, 0, 0, 0],
+            [-129, 0, 0, 0],
+            [0, -1, 0, 0],
+            [0, 65536, 0, 0],
+            [0, 0, i32::MAX as i64 + 1, 0],
+            [0, 0, 0, -1],
+        ] {
+            assert!(
+                matches!(encode(values), Err(DbError::OutOfRange { .. })),
+                "{:?}",
+                values
+            );
+        }
+        // bigint unsigned 超出 i64 的值是 decimal，按 u64 保存
+        let unsigned = |n: i128| {
+            let mut values = vec![Value::Int(0); 3];
+            values.push(Value::Decimal(Decimal::new(n, 0)));
+            rowmanager.from_parser(&[], values)
+        };
+        let bytes = unsigned(u64::MAX as i128).unwrap();
+        assert_eq!(
+            rowmanager.to_row(bytes)[3].to_string(),
+            "18446744073709551615"
+        );
+        assert!(matches!(
+            unsigned(u64::MAX as i128 + 1),
+            Err(DbError::OutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn float() {
+        let rowmanager =
+            RowManager::with_fields(fields("create table f (a float, b double, c boolean)"));
+        for values in [
+            [Value::Float(0.1), Value::Float(0.1), Value::Bool(true)],
+            [
+                Value::Float(-1.5e30),
+                Value::Float(f64::MAX),
+                Value::Bool(false),
+            ],
+            [
+                Value::Float(f64::NEG_INFINITY),
+                Value::Float(f64::INFINITY),
+                Value::Bool(true),
+            ],
+        ] {
+            let bytes = rowmanager.from_parser(&[], values.to_vec()).unwrap();
+            // 1 + 空值列表 1 + 4 + 8 + 1
+            assert_eq!(bytes.len(), 15);
+            assert_eq!(rowmanager.to_row(bytes), values);
+        }
+        let bytes = rowmanager
+            .from_parser(
+                &[],
+                vec![Value::Int(3), Value::Float(f64::NAN), Value::Int(1)],
+            )
+            .unwrap();
+        let res = rowmanager.to_row(bytes);
+        assert_eq!(res[0], Value::Float(3.0));
+        assert!(matches!(res[1], Value::Float(n) if n.is_nan()));
+        assert_eq!(res[2], Value::Bool(true));
+
+        let err = rowmanager.from_parser(&[], vec![Value::Float(1e39)]);
+        assert!(matches!(err, Err(DbError::OutOfRange { .. })));
+        let err = rowmanager.from_parser(&[], vec![Value::Null, Value::Str("1".to_string())]);
+        assert!(matches!(err, Err(DbError::TypeMismatch { .. })));
+        let err = rowmanager.from_parser(&[], vec![Value::Null, Value::Null, Value::Int(2)]);
+        assert!(matches!(err, Err(DbError::TypeMismatch { .. })));
+    }
+
+    #[test]
+    fn overflow() {
+        let rowmanager = RowManager::with_fields(fields(
+            "create table b (id int, body text, data blob, note varchar(5))",
+        ));
+        let body = "长文本".repeat(100);
+        let data: Vec<u8> = (0..=255).collect();
+        let values = vec![
+            Value::Int(1),
+            Value::Str(body.clone()),
+            Value::Bytes(data.clone()),
+            Value::Str("ab".to_string()),
+        ];
+        let bytes = rowmanager.from_parser(&[], values.clone()).unwrap();
+        // 1 + 变长字段列表 1 + 空值列表 1 + 4 + 16 + 16 + 2
+        assert_eq!(rowmanager.stored_len(&bytes), 41);
+        assert_eq!(bytes.len(), 41 + body.len() + data.len());
+        assert_eq!(rowmanager.to_row(bytes.clone()), values);
+
+        let path =
+            std::env::temp_dir().join(format!("rs_sql_row_overflow_{}.db", std::process::id()));
+        let mut pages = PageManager::create(path.to_str().unwrap());
+        let stored = rowmanager.spill(bytes, &mut pages);
+        assert_eq!(stored.len(), 41);
+        assert_eq!(rowmanager.read(stored.clone(), &pages), values);
+        // 释放的溢出页会被再次使用
+        rowmanager.release(&stored, &mut pages);
+        let max_page_id = pages.max_page_id;
+        let stored = rowmanager.spill(
+            rowmanager.from_parser(&[], values.clone()).unwrap(),
+            &mut pages,
+        );
+        assert_eq!(pages.max_page_id, max_page_id);
+        assert_eq!(rowmanager.read(stored, &pages), values);
+
+        // 空值和 NULL 不占用溢出页，blob 也可以写成字符串
+        let values = vec![
+            Value::Int(2),
+            Value::Str(String::new()),
+            Value::Null,
+            Value::Null,
+        ];
+        let stored = rowmanager.spill(
+            rowmanager.from_parser(&[], values.clone()).unwrap(),
+            &mut pages,
+        );
+        assert_eq!(rowmanager.read(stored, &pages), values);
+        let bytes = rowmanager
+            .from_parser(&[], vec![Value::Int(3), Value::Null, Value::from("ab")])
+            .unwrap();
+        assert_eq!(rowmanager.to_row(bytes)[2], Value::Bytes(b"ab".to_vec()));
+        let err = rowmanager.from_parser(&[], vec![Value::Int(3), Value::Bytes(vec![1])]);
+        assert!(matches!(err, Err(DbError::TypeMismatch { .. })));
+        let _ = std::fs::remove_file(&path);
+    }
+
     #[test]
     fn a2() {
         let a = [1, 2, 3];
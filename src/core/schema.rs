@@ -0,0 +1,51 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::parser::ast::{CreateTable, Field};
+
+// 表结构，序列化后保存在 <table>.frm 文件中
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Schema {
+    pub table: String,
+    pub ptable: Vec<Field>,
+    // 没有主键的旧表结构文件中没有这一项
+    #[serde(default)]
+    pub primary_key: Option<String>,
+    #[serde(default)]
+    pub indexes: Vec<IndexSchema>,
+}
+
+// create [unique] index name on t (col, ...)，索引的数据保存在 <table>.<name>.idx 中
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IndexSchema {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub unique: bool,
+}
+
+impl Schema {
+    pub fn new(create: &CreateTable) -> Self {
+        Schema {
+            table: create.table.clone(),
+            ptable: create.fields.clone(),
+            primary_key: create.primary_key.clone(),
+            indexes: vec![],
+        }
+    }
+
+    pub fn de(name: &str) -> Self {
+        let f = File::open(name).unwrap();
+        let mut s = String::new();
+        let mut reader = BufReader::new(f);
+        reader.read_to_string(&mut s).unwrap();
+        serde_json::from_str::<Self>(&s).unwrap()
+    }
+
+    pub fn se(&self, name: &str) {
+        let sered = serde_json::to_string(self).unwrap();
+        let mut f = File::create(name).unwrap();
+        f.write_all(sered.as_bytes()).unwrap();
+    }
+}
@@ -0,0 +1,278 @@
+use super::value::Value;
+
+// 日期和时间类型
+// date 保存为 1970-01-01 之后的天数（i32），time 保存为 0 点之后的微秒数，
+// datetime 和 timestamp 保存为 1970-01-01 00:00:00 之后的微秒数（i64），
+// 数值的顺序就是时间的先后顺序
+//
+// 字面量按 ISO-8601 解析：
+//   date      2024-02-29
+//   time      13:45、13:45:30、13:45:30.123456
+//   datetime  2024-02-29 13:45:30，日期和时间之间也可以是 T
+//   timestamp 同 datetime，可以带时区 Z、+08:00、-0530，保存时转为 UTC
+
+pub const MICROS_PER_SECOND: i64 = 1_000_000;
+pub const MICROS_PER_DAY: i64 = 86_400 * MICROS_PER_SECOND;
+
+pub fn is_temporal(fieldtype: &str) -> bool {
+    matches!(fieldtype, "date" | "time" | "datetime" | "timestamp")
+}
+
+// 把值转为字段类型对应的日期或时间，字符串按 ISO-8601 解析，
+// date 可以转为当天 0 点的 datetime，其余情况返回 None
+pub fn convert(fieldtype: &str, value: &Value) -> Option<Value> {
+    match (fieldtype, value) {
+        ("date", Value::Date(_)) | ("time", Value::Time(_)) => Some(value.clone()),
+        ("datetime" | "timestamp", Value::DateTime(_)) => Some(value.clone()),
+        ("datetime" | "timestamp", Value::Date(days)) => {
+            Some(Value::DateTime(*days as i64 * MICROS_PER_DAY))
+        }
+        ("date", Value::Str(s)) => parse_date(s).map(Value::Date),
+        ("time", Value::Str(s)) => parse_time(s).map(Value::Time),
+        ("datetime", Value::Str(s)) => parse_datetime(s, false).map(Value::DateTime),
+        ("timestamp", Value::Str(s)) => parse_datetime(s, true).map(Value::DateTime),
+        _ => None,
+    }
+}
+
+pub fn parse_date(s: &str) -> Option<i32> {
+    let b = s.as_bytes();
+    if b.len() != 10 || b[4] != b'-' || b[7] != b'-' {
+        return None;
+    }
+    let year = digits(&b[0..4])?;
+    let month = digits(&b[5..7])?;
+    let day = digits(&b[8..10])?;
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+    Some(days_from_civil(year, month, day) as i32)
+}
+
+pub fn parse_time(s: &str) -> Option<i64> {
+    let b = s.as_bytes();
+    if b.len() < 5 || b[2] != b':' {
+        return None;
+    }
+    let hour = digits(&b[0..2])?;
+    let minute = digits(&b[3..5])?;
+    let mut second = 0;
+    let mut micros = 0;
+    let rest = &b[5..];
+    if !rest.is_empty() {
+        if rest.len() < 3 || rest[0] != b':' {
+            return None;
+        }
+        second = digits(&rest[1..3])?;
+        let fraction = &rest[3..];
+        if !fraction.is_empty() {
+            // 小数部分最多 6 位，精确到微秒
+            if fraction[0] != b'.' || fraction.len() < 2 || fraction.len() > 7 {
+                return None;
+            }
+            micros = digits(&fraction[1..])? * 10i64.pow(7 - fraction.len() as u32);
+        }
+    }
+    if hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    Some(((hour * 60 + minute) * 60 + second) * MICROS_PER_SECOND + micros)
+}
+
+// 只有日期时为当天 0 点；with_zone 为 true 时可以带时区
+pub fn parse_datetime(s: &str, with_zone: bool) -> Option<i64> {
+    if s.len() == 10 {
+        return Some(parse_date(s)? as i64 * MICROS_PER_DAY);
+    }
+    if s.len() < 11 || !matches!(s.as_bytes()[10], b' ' | b'T') {
+        return None;
+    }
+    let days = parse_date(s.get(..10)?)? as i64;
+    let (time, offset) = split_zone(s.get(11..)?);
+    if offset.is_some() && !with_zone {
+        return None;
+    }
+    let offset = match offset {
+        Some(zone) => parse_zone(zone)?,
+        None => 0,
+    };
+    Some(days * MICROS_PER_DAY + parse_time(time)? - offset)
+}
+
+pub fn format_date(days: i32) -> String {
+    let (year, month, day) = civil_from_days(days as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// 没有小数部分时不输出小数
+pub fn format_time(micros: i64) -> String {
+    let seconds = micros / MICROS_PER_SECOND;
+    let mut s = format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+    let fraction = micros % MICROS_PER_SECOND;
+    if fraction != 0 {
+        s.push_str(format!(".{:06}", fraction).trim_end_matches('0'));
+    }
+    s
+}
+
+pub fn format_datetime(micros: i64) -> String {
+    let days = micros.div_euclid(MICROS_PER_DAY);
+    format!(
+        "{} {}",
+        format_date(days as i32),
+        format_time(micros.rem_euclid(MICROS_PER_DAY))
+    )
+}
+
+// 时区写在最后: Z、+08:00、-0530
+fn split_zone(s: &str) -> (&str, Option<&str>) {
+    if let Some(time) = s.strip_suffix('Z') {
+        return (time, Some("Z"));
+    }
+    match s.rfind(['+', '-']) {
+        Some(i) => (&s[..i], Some(&s[i..])),
+        None => (s, None),
+    }
+}
+
+// 时区相对 UTC 的偏移，单位为微秒
+fn parse_zone(zone: &str) -> Option<i64> {
+    if zone == "Z" {
+        return Some(0);
+    }
+    let b = zone.as_bytes();
+    let (hour, minute) = match b.len() {
+        6 if b[3] == b':' => (digits(&b[1..3])?, digits(&b[4..6])?),
+        5 => (digits(&b[1..3])?, digits(&b[3..5])?),
+        3 => (digits(&b[1..3])?, 0),
+        _ => return None,
+    };
+    if hour > 23 || minute > 59 {
+        return None;
+    }
+    let offset = (hour * 60 + minute) * 60 * MICROS_PER_SECOND;
+    Some(if b[0] == b'-' { -offset } else { offset })
+}
+
+fn digits(b: &[u8]) -> Option<i64> {
+    if b.is_empty() || !b.iter().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(b.iter().fold(0, |n, c| n * 10 + (c - b'0') as i64))
+}
+
+fn is_leap(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// 公历日期和 1970-01-01 之后天数的互相转换，每 400 年是一个 146097 天的周期
+// 从 3 月开始计算一年，闰日在年末
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn date() {
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(parse_date("1969-12-31"), Some(-1));
+        assert_eq!(parse_date("2000-03-01"), Some(11017));
+        for s in [
+            "0001-01-01",
+            "1900-02-28",
+            "2000-02-29",
+            "2024-12-31",
+            "9999-12-31",
+        ] {
+            assert_eq!(format_date(parse_date(s).unwrap()), s);
+        }
+        for s in [
+            "1900-02-29",
+            "2023-02-29",
+            "2024-04-31",
+            "2024-13-01",
+            "2024-1-01",
+            "24-01-01",
+        ] {
+            assert_eq!(parse_date(s), None, "{}", s);
+        }
+    }
+
+    #[test]
+    fn time() {
+        assert_eq!(parse_time("00:00"), Some(0));
+        assert_eq!(parse_time("01:02:03.5"), Some(3_723_500_000));
+        assert_eq!(
+            format_time(parse_time("23:59:59.000123").unwrap()),
+            "23:59:59.000123"
+        );
+        assert_eq!(format_time(parse_time("13:45").unwrap()), "13:45:00");
+        for s in [
+            "24:00",
+            "12:60",
+            "12:00:60",
+            "12:00:00.",
+            "12:00:00.1234567",
+            "1:00",
+        ] {
+            assert_eq!(parse_time(s), None, "{}", s);
+        }
+    }
+
+    #[test]
+    fn datetime() {
+        let t = parse_datetime("2024-02-29T13:45:30", false).unwrap();
+        assert_eq!(format_datetime(t), "2024-02-29 13:45:30");
+        assert_eq!(parse_datetime("2024-02-29 13:45:30", false), Some(t));
+        assert_eq!(
+            parse_datetime("2024-02-29", false),
+            Some(parse_date("2024-02-29").unwrap() as i64 * MICROS_PER_DAY)
+        );
+        // 带时区的值转为 UTC，datetime 不接受时区
+        assert_eq!(parse_datetime("2024-02-29 21:45:30+08:00", true), Some(t));
+        assert_eq!(parse_datetime("2024-02-29T08:15:30-0530", true), Some(t));
+        assert_eq!(parse_datetime("2024-02-29T13:45:30Z", true), Some(t));
+        assert_eq!(parse_datetime("2024-02-29T13:45:30Z", false), None);
+        assert_eq!(parse_datetime("2024-02-29 13:45:30+8", true), None);
+        assert_eq!(
+            format_datetime(parse_datetime("1969-12-31 23:59:59.5", false).unwrap()),
+            "1969-12-31 23:59:59.5"
+        );
+    }
+}
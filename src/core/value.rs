@@ -0,0 +1,353 @@
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+
+use serde_derive::{Deserialize, Serialize};
+
+use super::decimal::Decimal;
+use super::temporal;
+
+// sql 里的字面量，以及从行数据中解析出来的值
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub enum Value {
+    Null,
+    Int(i64),
+    Float(f64),
+    Decimal(Decimal),
+    Str(String),
+    Bytes(Vec<u8>), // blob 的值
+    Bool(bool),
+    Date(i32),     // 1970-01-01 之后的天数
+    Time(i64),     // 0 点之后的微秒数
+    DateTime(i64), // 1970-01-01 00:00:00 之后的微秒数
+}
+
+impl Value {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    // 类型名，用于报错
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Int(_) => "integer",
+            Value::Float(_) => "float",
+            Value::Decimal(_) => "decimal",
+            Value::Str(_) => "string",
+            Value::Bytes(_) => "blob",
+            Value::Bool(_) => "boolean",
+            Value::Date(_) => "date",
+            Value::Time(_) => "time",
+            Value::DateTime(_) => "datetime",
+        }
+    }
+}
+
+impl Value {
+    // 比较两个值，有 NULL 或者类型无法比较时返回 None
+    // 数字和字符串比较时，字符串按数字解析；
+    // 日期和时间按先后比较，和字符串比较时字符串按另一边的类型解析；
+    // decimal 和整数、decimal 以及字符串比较时不经过浮点数，结果是精确的；
+    // blob 和 blob、字符串按字节比较；
+    // 浮点数的 NaN 和 NaN 相等，并且大于其他所有数字，这样排序和索引的顺序是确定的
+    pub fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Null, _) | (_, Value::Null) => None,
+            (a, b) if a.is_temporal() || b.is_temporal() => Self::compare_temporal(a, b),
+            (Value::Decimal(_), _) | (_, Value::Decimal(_)) => {
+                match (self.as_decimal(), other.as_decimal()) {
+                    (Some(a), Some(b)) => Some(a.cmp(&b)),
+                    _ => Some(Self::compare_f64(self.as_f64()?, other.as_f64()?)),
+                }
+            }
+            (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+            (Value::Str(a), Value::Str(b)) => Some(a.cmp(b)),
+            (Value::Bytes(a), Value::Bytes(b)) => Some(a.cmp(b)),
+            (Value::Bytes(a), Value::Str(b)) => Some(a.as_slice().cmp(b.as_bytes())),
+            (Value::Str(a), Value::Bytes(b)) => Some(a.as_bytes().cmp(b)),
+            (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+            (a, b) => Some(Self::compare_f64(a.as_f64()?, b.as_f64()?)),
+        }
+    }
+
+    fn compare_f64(a: f64, b: f64) -> Ordering {
+        match (a.is_nan(), b.is_nan()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => a.partial_cmp(&b).unwrap(),
+        }
+    }
+
+    fn is_temporal(&self) -> bool {
+        matches!(self, Value::Date(_) | Value::Time(_) | Value::DateTime(_))
+    }
+
+    // date 和 datetime 比较时 date 看作当天 0 点；
+    // date 和带时间的字符串比较时也按 datetime 比较；字符串可以带时区，和 timestamp 一样转为 UTC
+    fn compare_temporal(a: &Value, b: &Value) -> Option<Ordering> {
+        let kind = match (a, b) {
+            (Value::Time(_), _) | (_, Value::Time(_)) => "time",
+            (Value::DateTime(_), _) | (_, Value::DateTime(_)) => "timestamp",
+            _ => "date",
+        };
+        let convert = |kind| Some((temporal::convert(kind, a)?, temporal::convert(kind, b)?));
+        let pair = match convert(kind) {
+            None if kind == "date" => convert("timestamp"),
+            pair => pair,
+        };
+        match pair? {
+            (Value::Date(a), Value::Date(b)) => Some(a.cmp(&b)),
+            (Value::Time(a), Value::Time(b)) | (Value::DateTime(a), Value::DateTime(b)) => {
+                Some(a.cmp(&b))
+            }
+            _ => None,
+        }
+    }
+
+    // 整数、decimal 和能按 decimal 解析的字符串
+    pub fn as_decimal(&self) -> Option<Decimal> {
+        match self {
+            Value::Int(n) => Some(Decimal::from(*n)),
+            Value::Decimal(d) => Some(*d),
+            Value::Str(s) => Decimal::parse(s),
+            _ => None,
+        }
+    }
+
+    // 和某个整数相等的值对应的整数，decimal、浮点数和字符串不能有小数部分；
+    // 浮点数和字符串按 f64 比较，绝对值达到 2^53 时可能和多个整数相等，返回 None
+    pub fn as_integer(&self) -> Option<i128> {
+        match self {
+            Value::Int(n) => Some(*n as i128),
+            Value::Bool(b) => Some(*b as i128),
+            Value::Decimal(d) => {
+                let d = d.normalize();
+                (d.scale() == 0).then_some(d.value())
+            }
+            Value::Float(_) | Value::Str(_) => {
+                let n = self.as_f64()?;
+                (n.fract() == 0.0 && n.abs() < 2f64.powi(53)).then_some(n as i128)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(n) => Some(*n as f64),
+            Value::Float(n) => Some(*n),
+            Value::Decimal(d) => Some(d.to_f64()),
+            Value::Str(s) => s.trim().parse().ok(),
+            Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+            Value::Null
+            | Value::Bytes(_)
+            | Value::Date(_)
+            | Value::Time(_)
+            | Value::DateTime(_) => None,
+        }
+    }
+
+    // 作为条件时的真假，NULL 为 None
+    pub fn to_bool(&self) -> Option<bool> {
+        match self {
+            Value::Null => None,
+            Value::Bool(b) => Some(*b),
+            Value::Int(n) => Some(*n != 0),
+            Value::Float(n) => Some(*n != 0.0),
+            Value::Decimal(d) => Some(d.value() != 0),
+            Value::Str(s) => Some(s.trim().parse::<f64>().is_ok_and(|n| n != 0.0)),
+            Value::Bytes(b) => Value::Str(String::from_utf8_lossy(b).to_string()).to_bool(),
+            Value::Date(_) | Value::Time(_) | Value::DateTime(_) => Some(true),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "NULL"),
+            Value::Int(n) => write!(f, "{}", n),
+            Value::Float(n) => write!(f, "{}", n),
+            Value::Decimal(d) => write!(f, "{}", d),
+            Value::Str(s) => write!(f, "{}", s),
+            // blob 按十六进制输出
+            Value::Bytes(b) => {
+                write!(f, "0x")?;
+                b.iter().try_for_each(|c| write!(f, "{:02x}", c))
+            }
+            Value::Bool(b) => write!(f, "{}", if *b { "TRUE" } else { "FALSE" }),
+            Value::Date(days) => write!(f, "{}", temporal::format_date(*days)),
+            Value::Time(micros) => write!(f, "{}", temporal::format_time(*micros)),
+            Value::DateTime(micros) => write!(f, "{}", temporal::format_datetime(*micros)),
+        }
+    }
+}
+
+// 预处理语句的参数可以直接用 Rust 的值，None 对应 NULL
+impl From<i64> for Value {
+    fn from(n: i64) -> Self {
+        Value::Int(n)
+    }
+}
+
+// 超出 i64 的值是小数位数为 0 的 decimal
+impl From<u64> for Value {
+    fn from(n: u64) -> Self {
+        match i64::try_from(n) {
+            Ok(n) => Value::Int(n),
+            Err(_) => Value::Decimal(Decimal::new(n as i128, 0)),
+        }
+    }
+}
+
+impl From<i32> for Value {
+    fn from(n: i32) -> Self {
+        Value::Int(n as i64)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Float(n)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(b: Vec<u8>) -> Self {
+        Value::Bytes(b)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Str(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::Str(s)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn compare() {
+        assert_eq!(Value::Int(1).compare(&Value::Int(2)), Some(Ordering::Less));
+        assert_eq!(
+            Value::Int(2).compare(&Value::Float(1.5)),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            Value::Str("10".to_string()).compare(&Value::Int(10)),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            Value::Str("b".to_string()).compare(&Value::Str("ab".to_string())),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(Value::Null.compare(&Value::Null), None);
+        assert_eq!(Value::Str("x".to_string()).compare(&Value::Int(1)), None);
+        assert_eq!(Value::Float(-3.0).as_integer(), Some(-3));
+        assert_eq!(Value::Str(" 4 ".to_string()).as_integer(), Some(4));
+        assert_eq!(Value::Float(1e300).as_integer(), None);
+    }
+
+    #[test]
+    fn compare_float() {
+        let nan = Value::Float(f64::NAN);
+        assert_eq!(nan.compare(&Value::Float(f64::NAN)), Some(Ordering::Equal));
+        assert_eq!(
+            nan.compare(&Value::Float(f64::INFINITY)),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(Value::Int(1).compare(&nan), Some(Ordering::Less));
+        assert_eq!(
+            Value::Float(-0.0).compare(&Value::Float(0.0)),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            Value::Bool(true).compare(&Value::Int(1)),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(nan.compare(&Value::Null), None);
+    }
+
+    #[test]
+    fn compare_bytes() {
+        let bytes = Value::Bytes(b"abc".to_vec());
+        assert_eq!(
+            bytes.compare(&Value::Bytes(b"abd".to_vec())),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            bytes.compare(&Value::Str("abc".to_string())),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(bytes.compare(&Value::Int(1)), None);
+        assert_eq!(Value::Bytes(vec![0, 0xff]).to_string(), "0x00ff");
+    }
+
+    #[test]
+    fn compare_decimal() {
+        let d = |s: &str| Value::Decimal(Decimal::parse(s).unwrap());
+        assert_eq!(d("1.50").compare(&d("1.5")), Some(Ordering::Equal));
+        assert_eq!(d("2.00").compare(&Value::Int(2)), Some(Ordering::Equal));
+        // 超出 f64 精度的值也能精确比较
+        assert_eq!(
+            d("12345678901234567.01").compare(&Value::Str("12345678901234567.02".to_string())),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            d("0.5").compare(&Value::Float(0.25)),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(d("0.5").compare(&Value::Str("x".to_string())), None);
+        assert_eq!(d("2.00").as_integer(), Some(2));
+        assert_eq!(d("2.50").as_integer(), None);
+    }
+
+    #[test]
+    fn compare_temporal() {
+        let date = Value::Date(temporal::parse_date("2024-03-01").unwrap());
+        let str = |s: &str| Value::Str(s.to_string());
+        assert_eq!(date.compare(&str("2024-02-29")), Some(Ordering::Greater));
+        assert_eq!(
+            date.compare(&str("2024-03-01 00:00:01")),
+            Some(Ordering::Less)
+        );
+        let midnight = temporal::parse_datetime("2024-03-01", false).unwrap();
+        assert_eq!(
+            date.compare(&Value::DateTime(midnight)),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            Value::Time(temporal::parse_time("09:30").unwrap()).compare(&str("10:00:00")),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            Value::DateTime(midnight).compare(&str("2024-03-01T08:00:00+08:00")),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(date.compare(&str("yesterday")), None);
+        assert_eq!(date.compare(&Value::Int(1)), None);
+        assert_eq!(date.to_string(), "2024-03-01");
+    }
+}
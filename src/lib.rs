@@ -1,14 +1,18 @@
+pub mod bptree;
 pub mod core;
 pub mod http;
 pub mod parser;
-pub mod bptree;
 
 #[cfg(test)]
 mod test {
 
     use crate::{
-        core::{page::PageManager, row::RowManager},
-        parser::{parser::Parser, token},
+        core::{page::PageManager, row::RowManager, schema::Schema},
+        parser::{
+            ast::{InsertSource, Statement},
+            parser::Parser,
+            token,
+        },
     };
 
     #[test]
@@ -21,11 +25,13 @@ mod test {
 name varchar(15) not null
 )";
         // let sql = "   \ninsert into user(id,col2,col3,col4,name)values(1,4,aaaaa,bbbb, cc)where id=1; ";
-        let token_stream = token::trim_to_token_stream(&token::trim_code(sql));
+        let token_stream = token::tokenize(sql);
         println!("{:#?}", token_stream);
-        let mut parser: Parser = Parser::new();
-        parser.parse(token_stream).execute();
-        println!("{:#?}", parser);
+        let statement = Parser::new(token_stream).parse().unwrap();
+        println!("{:#?}", statement);
+        if let Statement::CreateTable(create) = statement {
+            Schema::new(&create).se("user.frm");
+        }
         // let f = OpenOptions::new().read(true).open("student.db").unwrap();
         // let mut buf = [0; 8];
         // for i in 0..7 {
@@ -33,19 +39,27 @@ name varchar(15) not null
         //     println!("{}", usize::from_ne_bytes(buf));
         // }
 
-        let sql =
-            "   \ninsert into user(id,col2,col3,col4,name)values(1,4,aaaaa,bbbb, cc)where id=1; ";
-        let token_stream = token::trim_to_token_stream(&token::trim_code(sql));
-        let mut parser = Parser::new();
-        parser.parse(token_stream);
-        println!("{:?}", parser);
-        let mut rowmanager = RowManager::new("user.frm");
-        let bytes = rowmanager.from_parser(parser);
+        let sql = "   \ninsert into user(id,col2,col3,col4,name)values(1,4,'aaaaa','bbbb', 'cc'); ";
+        let token_stream = token::tokenize(sql);
+        let insert = match Parser::new(token_stream).parse().unwrap() {
+            Statement::Insert(insert) => insert,
+            _ => unreachable!(),
+        };
+        println!("{:?}", insert);
+        let values = match &insert.source {
+            InsertSource::Values(rows) => rows[0]
+                .iter()
+                .map(|expr| expr.as_literal().unwrap().clone())
+                .collect(),
+            InsertSource::Select(_) => unreachable!(),
+        };
+        let rowmanager = RowManager::new("user.frm");
+        let bytes = rowmanager.from_parser(&insert.columns, values).unwrap();
         println!("{:?}", bytes);
 
         let mut pagemanager = PageManager::read_file("user.db");
         pagemanager.insert(1, bytes);
-        let res = pagemanager.select_recursive(0, 1);
+        let res = pagemanager.select(1);
         let res = rowmanager.to_row(res.unwrap());
         println!("{:?}", res);
     }
@@ -0,0 +1,111 @@
+use std::process;
+
+use clap::{App, Arg, SubCommand};
+
+use lists::core::error::DbError;
+use lists::core::executor::Executor;
+use lists::parser::parser::Parser;
+use lists::parser::token;
+
+fn main() {
+    let matches = App::new("my-rust-db")
+        .arg(
+            Arg::with_name("dir")
+                .long("dir")
+                .takes_value(true)
+                .default_value("data")
+                .help("directory that holds the databases and table files"),
+        )
+        .arg(
+            Arg::with_name("database")
+                .long("database")
+                .short("D")
+                .takes_value(true)
+                .help("database to use before running the statements"),
+        )
+        .subcommand(
+            SubCommand::with_name("run")
+                .about("runs the statements in sql files in order")
+                .arg(Arg::with_name("FILE").required(true).multiple(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("exec")
+                .about("runs the statements given on the command line")
+                .arg(Arg::with_name("SQL").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("format")
+                .about("prints the statements in sql files as formatted sql")
+                .arg(Arg::with_name("FILE").required(true).multiple(true)),
+        )
+        .get_matches();
+
+    // 格式化不需要打开数据库
+    if let ("format", Some(args)) = matches.subcommand() {
+        for file_name in args.values_of("FILE").unwrap() {
+            format(file_name, &read(file_name));
+        }
+        return;
+    }
+    let mut executor = Executor::new(matches.value_of("dir").unwrap());
+    if let Some(database) = matches.value_of("database") {
+        if let Err(err) = executor.use_database(database) {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    }
+    match matches.subcommand() {
+        ("run", Some(args)) => {
+            for file_name in args.values_of("FILE").unwrap() {
+                run(&mut executor, file_name, &read(file_name));
+            }
+        }
+        ("exec", Some(args)) => run(&mut executor, "<sql>", args.value_of("SQL").unwrap()),
+        _ => {
+            eprintln!("{}", matches.usage());
+            process::exit(2);
+        }
+    }
+}
+
+fn read(file_name: &str) -> String {
+    std::fs::read_to_string(file_name).unwrap_or_else(|err| {
+        eprintln!("{}: {}", file_name, err);
+        process::exit(1);
+    })
+}
+
+// 每条语句按多行格式输出，以 ; 结尾，语句之间空一行
+fn format(name: &str, sql: &str) {
+    match Parser::new(token::tokenize(sql)).parse_script() {
+        Ok(statements) => {
+            let statements: Vec<String> = statements
+                .iter()
+                .map(|statement| format!("{:#};", statement))
+                .collect();
+            println!("{}", statements.join("\n\n"));
+        }
+        Err(err) => {
+            eprintln!("{}: {}", name, err.render(sql));
+            process::exit(1);
+        }
+    }
+}
+
+fn run(executor: &mut Executor, name: &str, sql: &str) {
+    match executor.run_script(sql) {
+        Ok(results) => {
+            for res in results {
+                println!("{}", res);
+            }
+        }
+        Err(DbError::Parse(err)) => {
+            eprintln!("{}: {}", name, err.render(sql));
+            process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("{}: {}", name, err);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,526 @@
+use serde_derive::{Deserialize, Serialize};
+
+use crate::core::value::Value;
+
+// 建表语句中的一个字段
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Field {
+    pub value: String,
+    pub fieldtype: String,
+    pub bitsize: usize, // 整数类型为字节数，decimal 为总位数
+    pub can_null: bool,
+    #[serde(default)]
+    pub scale: usize, // decimal 小数部分的位数，其余类型为 0
+    #[serde(default)]
+    pub unsigned: bool, // 整数类型是否为 unsigned
+}
+
+impl Field {
+    // tinyint、smallint、int、bigint，按 bitsize 个字节保存
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self.fieldtype.as_str(),
+            "tinyint" | "smallint" | "int" | "bigint"
+        )
+    }
+
+    // 整数类型能保存的最小值和最大值，bigint unsigned 的最大值超出 i64，所以用 i128
+    pub fn range(&self) -> (i128, i128) {
+        let bits = self.bitsize as u32 * 8;
+        if self.unsigned {
+            (0, (1 << bits) - 1)
+        } else {
+            (-(1 << (bits - 1)), (1 << (bits - 1)) - 1)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    Select(Select),
+    Insert(Insert),
+    Update(Update),
+    Delete(Delete),
+    CreateTable(CreateTable),
+    DropTable(DropTable),
+    Truncate(String),
+    AlterTable(AlterTable),
+    CreateDatabase { name: String, if_not_exists: bool },
+    DropDatabase { name: String, if_exists: bool },
+    Use(String),
+    CreateIndex(CreateIndex),
+    DropIndex { name: String, table: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Select {
+    pub items: Vec<SelectItem>,
+    pub from: Option<TableRef>,
+    pub joins: Vec<Join>,
+    pub selection: Option<Expr>,
+    pub group_by: Vec<Expr>,
+    pub having: Option<Expr>,
+    pub order_by: Vec<OrderBy>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectItem {
+    Wildcard, // *
+    Expr { expr: Expr, alias: Option<String> },
+}
+
+// order by 中的一项，默认升序
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderBy {
+    pub expr: Expr,
+    pub desc: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableRef {
+    pub name: String,
+    pub alias: Option<String>,
+}
+
+impl TableRef {
+    // 列名前面用来限定的名字，有别名时只能用别名
+    pub fn qualifier(&self) -> &str {
+        self.alias.as_ref().unwrap_or(&self.name)
+    }
+}
+
+// [inner | left [outer]] join table on expr
+#[derive(Debug, Clone, PartialEq)]
+pub struct Join {
+    pub kind: JoinKind,
+    pub table: TableRef,
+    pub on: Expr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JoinKind {
+    Inner,
+    Left,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Insert {
+    pub table: String,
+    pub columns: Vec<String>, // 为空时按表结构的字段顺序
+    pub source: InsertSource,
+}
+
+// values (1, 'a'), (2, 'b') 或者 select ...
+#[derive(Debug, Clone, PartialEq)]
+pub enum InsertSource {
+    Values(Vec<Vec<Expr>>),
+    Select(Box<Select>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Update {
+    pub table: String,
+    pub assignments: Vec<(String, Expr)>,
+    pub selection: Option<Expr>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delete {
+    pub table: String,
+    pub selection: Option<Expr>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreateTable {
+    pub table: String,
+    pub fields: Vec<Field>,
+    pub primary_key: Option<String>,
+}
+
+// create [unique] index name on t (col, ...)
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreateIndex {
+    pub name: String,
+    pub table: String,
+    pub columns: Vec<String>,
+    pub unique: bool,
+}
+
+// drop table [if exists] t
+#[derive(Debug, Clone, PartialEq)]
+pub struct DropTable {
+    pub table: String,
+    pub if_exists: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlterTable {
+    pub table: String,
+    pub operation: AlterOperation,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlterOperation {
+    // add [column] name type
+    AddColumn(Field),
+    // drop [column] name
+    DropColumn(String),
+    // rename column a to b
+    RenameColumn { from: String, to: String },
+    // rename [to] name
+    RenameTable(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    // 预处理语句的参数，从 0 开始编号
+    Placeholder(usize),
+    Column {
+        table: Option<String>,
+        name: String,
+    },
+    Binary {
+        left: Box<Expr>,
+        op: BinaryOp,
+        right: Box<Expr>,
+    },
+    Unary {
+        op: UnaryOp,
+        expr: Box<Expr>,
+    },
+    // 聚合函数，arg 为 None 表示 count(*)
+    Aggregate {
+        func: AggregateFunc,
+        arg: Option<Box<Expr>>,
+    },
+    // 返回一行一列的子查询
+    Subquery(Box<Select>),
+    Exists(Box<Select>),
+    // expr [not] in (select ...)
+    InSubquery {
+        expr: Box<Expr>,
+        subquery: Box<Select>,
+        negated: bool,
+    },
+    // expr [not] in (a, b, c)
+    InList {
+        expr: Box<Expr>,
+        list: Vec<Expr>,
+        negated: bool,
+    },
+    // expr [not] between low and high
+    Between {
+        expr: Box<Expr>,
+        low: Box<Expr>,
+        high: Box<Expr>,
+        negated: bool,
+    },
+    // expr [not] like pattern [escape c]，% 匹配任意个字符，_ 匹配一个字符
+    Like {
+        expr: Box<Expr>,
+        pattern: Box<Expr>,
+        escape: Option<Box<Expr>>,
+        negated: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnaryOp {
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AggregateFunc {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl AggregateFunc {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "count" => Some(AggregateFunc::Count),
+            "sum" => Some(AggregateFunc::Sum),
+            "avg" => Some(AggregateFunc::Avg),
+            "min" => Some(AggregateFunc::Min),
+            "max" => Some(AggregateFunc::Max),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AggregateFunc::Count => "count",
+            AggregateFunc::Sum => "sum",
+            AggregateFunc::Avg => "avg",
+            AggregateFunc::Min => "min",
+            AggregateFunc::Max => "max",
+        }
+    }
+}
+
+impl Statement {
+    // 用 f 替换语句中的每个占位符，预处理语句用它检查参数个数和绑定参数
+    pub fn map_placeholders<E>(
+        &self,
+        f: &mut dyn FnMut(usize) -> Result<Expr, E>,
+    ) -> Result<Statement, E> {
+        Ok(match self {
+            Statement::Select(select) => Statement::Select(select.map_placeholders(f)?),
+            Statement::Insert(insert) => Statement::Insert(Insert {
+                source: match &insert.source {
+                    InsertSource::Values(rows) => InsertSource::Values(
+                        rows.iter()
+                            .map(|row| {
+                                row.iter()
+                                    .map(|expr| expr.map_placeholders(f))
+                                    .collect::<Result<Vec<_>, E>>()
+                            })
+                            .collect::<Result<Vec<_>, E>>()?,
+                    ),
+                    InsertSource::Select(select) => {
+                        InsertSource::Select(Box::new(select.map_placeholders(f)?))
+                    }
+                },
+                ..insert.clone()
+            }),
+            Statement::Update(update) => Statement::Update(Update {
+                table: update.table.clone(),
+                assignments: update
+                    .assignments
+                    .iter()
+                    .map(|(name, expr)| Ok((name.clone(), expr.map_placeholders(f)?)))
+                    .collect::<Result<Vec<_>, E>>()?,
+                selection: update
+                    .selection
+                    .as_ref()
+                    .map(|expr| expr.map_placeholders(f))
+                    .transpose()?,
+            }),
+            Statement::Delete(delete) => Statement::Delete(Delete {
+                table: delete.table.clone(),
+                selection: delete
+                    .selection
+                    .as_ref()
+                    .map(|expr| expr.map_placeholders(f))
+                    .transpose()?,
+            }),
+            statement => statement.clone(),
+        })
+    }
+}
+
+impl Select {
+    pub fn map_placeholders<E>(
+        &self,
+        f: &mut dyn FnMut(usize) -> Result<Expr, E>,
+    ) -> Result<Select, E> {
+        let mut select = self.clone();
+        for item in select.items.iter_mut() {
+            if let SelectItem::Expr { expr, .. } = item {
+                *expr = expr.map_placeholders(f)?;
+            }
+        }
+        for join in select.joins.iter_mut() {
+            join.on = join.on.map_placeholders(f)?;
+        }
+        for expr in select.group_by.iter_mut() {
+            *expr = expr.map_placeholders(f)?;
+        }
+        for order in select.order_by.iter_mut() {
+            order.expr = order.expr.map_placeholders(f)?;
+        }
+        for expr in select.selection.iter_mut().chain(select.having.iter_mut()) {
+            *expr = expr.map_placeholders(f)?;
+        }
+        Ok(select)
+    }
+}
+
+impl Expr {
+    pub fn column(name: &str) -> Self {
+        Expr::Column {
+            table: None,
+            name: name.to_string(),
+        }
+    }
+
+    pub fn qualified(table: &str, name: &str) -> Self {
+        Expr::Column {
+            table: Some(table.to_string()),
+            name: name.to_string(),
+        }
+    }
+
+    pub fn binary(left: Expr, op: BinaryOp, right: Expr) -> Self {
+        Expr::Binary {
+            left: Box::new(left),
+            op,
+            right: Box::new(right),
+        }
+    }
+
+    pub fn negate(expr: Expr) -> Self {
+        Expr::Unary {
+            op: UnaryOp::Not,
+            expr: Box::new(expr),
+        }
+    }
+
+    // 直接包含的子表达式，不包括子查询里的表达式
+    pub fn children(&self) -> Vec<&Expr> {
+        match self {
+            Expr::Literal(_)
+            | Expr::Placeholder(_)
+            | Expr::Column { .. }
+            | Expr::Subquery(_)
+            | Expr::Exists(_) => vec![],
+            Expr::Binary { left, right, .. } => vec![left, right],
+            Expr::Unary { expr, .. } | Expr::InSubquery { expr, .. } => vec![expr],
+            Expr::Aggregate { arg, .. } => arg.iter().map(|arg| arg.as_ref()).collect(),
+            Expr::InList { expr, list, .. } => {
+                let mut children = vec![expr.as_ref()];
+                children.extend(list.iter());
+                children
+            }
+            Expr::Between {
+                expr, low, high, ..
+            } => vec![expr, low, high],
+            Expr::Like {
+                expr,
+                pattern,
+                escape,
+                ..
+            } => {
+                let mut children = vec![expr.as_ref(), pattern.as_ref()];
+                children.extend(escape.iter().map(|escape| escape.as_ref()));
+                children
+            }
+        }
+    }
+
+    // 用 f 替换每个直接包含的子表达式，得到新的表达式
+    pub fn map_children<E, F: FnMut(&Expr) -> Result<Expr, E>>(&self, mut f: F) -> Result<Expr, E> {
+        let mut boxed = |expr: &Expr| f(expr).map(Box::new);
+        Ok(match self {
+            Expr::Literal(_)
+            | Expr::Placeholder(_)
+            | Expr::Column { .. }
+            | Expr::Subquery(_)
+            | Expr::Exists(_) => self.clone(),
+            Expr::Binary { left, op, right } => Expr::Binary {
+                left: boxed(left)?,
+                op: *op,
+                right: boxed(right)?,
+            },
+            Expr::Unary { op, expr } => Expr::Unary {
+                op: *op,
+                expr: boxed(expr)?,
+            },
+            Expr::Aggregate { func, arg } => Expr::Aggregate {
+                func: *func,
+                arg: match arg {
+                    Some(arg) => Some(boxed(arg)?),
+                    None => None,
+                },
+            },
+            Expr::InSubquery {
+                expr,
+                subquery,
+                negated,
+            } => Expr::InSubquery {
+                expr: boxed(expr)?,
+                subquery: subquery.clone(),
+                negated: *negated,
+            },
+            Expr::InList {
+                expr,
+                list,
+                negated,
+            } => Expr::InList {
+                expr: boxed(expr)?,
+                list: list.iter().map(&mut f).collect::<Result<Vec<_>, E>>()?,
+                negated: *negated,
+            },
+            Expr::Between {
+                expr,
+                low,
+                high,
+                negated,
+            } => Expr::Between {
+                expr: boxed(expr)?,
+                low: boxed(low)?,
+                high: boxed(high)?,
+                negated: *negated,
+            },
+            Expr::Like {
+                expr,
+                pattern,
+                escape,
+                negated,
+            } => Expr::Like {
+                expr: boxed(expr)?,
+                pattern: boxed(pattern)?,
+                escape: match escape {
+                    Some(escape) => Some(boxed(escape)?),
+                    None => None,
+                },
+                negated: *negated,
+            },
+        })
+    }
+
+    // 表达式中是否含有聚合函数，子查询里的聚合函数属于子查询
+    pub fn has_aggregate(&self) -> bool {
+        match self {
+            Expr::Aggregate { .. } => true,
+            expr => expr.children().iter().any(|child| child.has_aggregate()),
+        }
+    }
+
+    // 用 f 替换表达式中的每个占位符，包括子查询中的
+    pub fn map_placeholders<E>(
+        &self,
+        f: &mut dyn FnMut(usize) -> Result<Expr, E>,
+    ) -> Result<Expr, E> {
+        match self {
+            Expr::Placeholder(i) => f(*i),
+            Expr::Subquery(select) => Ok(Expr::Subquery(Box::new(select.map_placeholders(f)?))),
+            Expr::Exists(select) => Ok(Expr::Exists(Box::new(select.map_placeholders(f)?))),
+            Expr::InSubquery {
+                expr,
+                subquery,
+                negated,
+            } => Ok(Expr::InSubquery {
+                expr: Box::new(expr.map_placeholders(f)?),
+                subquery: Box::new(subquery.map_placeholders(f)?),
+                negated: *negated,
+            }),
+            expr => expr.map_children(|child| child.map_placeholders(f)),
+        }
+    }
+
+    pub fn as_literal(&self) -> Option<&Value> {
+        match self {
+            Expr::Literal(v) => Some(v),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use std::fmt;
+
+// 语法错误，offset 和 length 是出错的 token 在原始 sql 中的字节位置
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub expected: String,
+    pub found: Option<String>, // None 表示已经到了输入末尾
+    pub offset: usize,
+    pub length: usize,
+}
+
+impl ParseError {
+    // 出错位置的行号和列号，都从 1 开始，列按字符计算
+    pub fn position(&self, sql: &str) -> (usize, usize) {
+        let before = &sql[..self.offset.min(sql.len())];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let column = before[line_start..].chars().count() + 1;
+        (line, column)
+    }
+
+    // 输出出错的那一行，并用 ^ 标出出错的 token
+    //
+    // expected `values` or `select`, found `value` at line 1, column 22
+    // insert into user(id) value (1)
+    //                      ^^^^^
+    pub fn render(&self, sql: &str) -> String {
+        let (line, column) = self.position(sql);
+        let text = sql.lines().nth(line - 1).unwrap_or("");
+        // 保留制表符，保证 ^ 和出错的 token 对齐
+        let padding: String = text
+            .chars()
+            .take(column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let width = match &self.found {
+            Some(_) => sql[self.offset..self.offset + self.length].chars().count(),
+            None => 1,
+        };
+        format!(
+            "{} at line {}, column {}\n{}\n{}{}",
+            self,
+            line,
+            column,
+            text,
+            padding,
+            "^".repeat(width.max(1))
+        )
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.found {
+            Some(found) => write!(f, "expected {}, found `{}`", self.expected, found),
+            None => write!(f, "expected {}, found end of input", self.expected),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod test {
+    use super::ParseError;
+
+    #[test]
+    fn render() {
+        let sql = "select *\nfrom user\nwhere\tid = = 1";
+        let err = ParseError {
+            expected: "expression".to_string(),
+            found: Some("=".to_string()),
+            offset: sql.rfind("= 1").unwrap(),
+            length: 1,
+        };
+        assert_eq!(err.position(sql), (3, 12));
+        assert_eq!(
+            err.render(sql),
+            "expected expression, found `=` at line 3, column 12\nwhere\tid = = 1\n     \t     ^"
+        );
+    }
+}
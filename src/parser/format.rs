@@ -0,0 +1,625 @@
+use std::fmt;
+
+use super::ast::*;
+use super::token;
+use crate::core::value::Value;
+
+// 把语法树还原为规范的 sql：关键字大写，标识符只在需要时用反引号包裹，
+// 字符串和浮点数的写法保证重新解析后得到相同的语法树
+//
+// {} 输出为一行，适合日志和错误信息；{:#} 每个子句单独一行，子查询缩进：
+//
+// SELECT id, name
+// FROM user AS u
+// WHERE id IN (
+//   SELECT id
+//   FROM t
+// )
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut writer = Writer::new(f.alternate());
+        writer.statement(self);
+        f.write_str(&writer.out)
+    }
+}
+
+impl fmt::Display for Select {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut writer = Writer::new(f.alternate());
+        writer.select(self);
+        f.write_str(&writer.out)
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut writer = Writer::new(f.alternate());
+        writer.expr(self, 0);
+        f.write_str(&writer.out)
+    }
+}
+
+// 标识符是小写字母、数字和下划线组成且不是关键字时原样输出，否则用反引号包裹
+pub fn identifier(name: &str) -> String {
+    let mut chars = name.chars();
+    let plain = chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+        && !name.chars().any(|c| c.is_ascii_uppercase())
+        && !token::is_reserved(name);
+    if plain {
+        name.to_string()
+    } else {
+        format!("`{}`", name.replace('`', "``"))
+    }
+}
+
+pub fn literal(value: &Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Int(n) => n.to_string(),
+        // 没有指数的小数是 decimal，浮点数总是写成指数形式，重新解析后仍然是浮点数
+        Value::Float(n) => format!("{:e}", n),
+        Value::Bool(b) => if *b { "TRUE" } else { "FALSE" }.to_string(),
+        Value::Bytes(b) => {
+            let hex: String = b.iter().map(|c| format!("{:02X}", c)).collect();
+            format!("X'{}'", hex)
+        }
+        // 有小数位的 decimal 直接写成小数，重新解析后小数位数不变
+        Value::Decimal(d) if d.scale() > 0 => value.to_string(),
+        Value::Decimal(_) => format!("DECIMAL {}", literal(&Value::Str(value.to_string()))),
+        Value::Date(_) => format!("DATE {}", literal(&Value::Str(value.to_string()))),
+        Value::Time(_) => format!("TIME {}", literal(&Value::Str(value.to_string()))),
+        Value::DateTime(_) => format!("DATETIME {}", literal(&Value::Str(value.to_string()))),
+        Value::Str(s) => {
+            let mut quoted = String::from("'");
+            for c in s.chars() {
+                match c {
+                    '\'' => quoted.push_str("''"),
+                    '\\' => quoted.push_str("\\\\"),
+                    '\n' => quoted.push_str("\\n"),
+                    '\r' => quoted.push_str("\\r"),
+                    '\t' => quoted.push_str("\\t"),
+                    '\0' => quoted.push_str("\\0"),
+                    c => quoted.push(c),
+                }
+            }
+            quoted.push('\'');
+            quoted
+        }
+    }
+}
+
+// 字段定义，如 name VARCHAR(15) NOT NULL
+fn field(field: &Field) -> String {
+    let mut s = format!("{} {}", identifier(&field.value), field_type(field));
+    if !field.can_null {
+        s.push_str(" NOT NULL");
+    }
+    s
+}
+
+fn field_type(field: &Field) -> String {
+    let name = field.fieldtype.to_ascii_uppercase();
+    match field.fieldtype.as_str() {
+        "char" | "varchar" => format!("{}({})", name, field.bitsize),
+        "decimal" => format!("{}({}, {})", name, field.bitsize, field.scale),
+        _ if field.unsigned => format!("{} UNSIGNED", name),
+        _ => name,
+    }
+}
+
+fn identifier_list(names: &[String]) -> String {
+    let names: Vec<String> = names.iter().map(|name| identifier(name)).collect();
+    format!("({})", names.join(", "))
+}
+
+// 运算的优先级，和 Parser 中的层次一致: or < and < not < 比较运算 < primary
+fn precedence(expr: &Expr) -> u8 {
+    match expr {
+        Expr::Binary {
+            op: BinaryOp::Or, ..
+        } => 1,
+        Expr::Binary {
+            op: BinaryOp::And, ..
+        } => 2,
+        Expr::Unary { .. } => 3,
+        Expr::Binary { .. }
+        | Expr::InSubquery { .. }
+        | Expr::InList { .. }
+        | Expr::Between { .. }
+        | Expr::Like { .. } => 4,
+        _ => 5,
+    }
+}
+
+const PRIMARY: u8 = 5;
+
+struct Writer {
+    out: String,
+    pretty: bool,
+    indent: usize,
+}
+
+impl Writer {
+    fn new(pretty: bool) -> Self {
+        Writer {
+            out: String::new(),
+            pretty,
+            indent: 0,
+        }
+    }
+
+    fn push(&mut self, s: &str) {
+        self.out.push_str(s);
+    }
+
+    // 子句之间的分隔，多行格式时换行并缩进
+    fn separator(&mut self) {
+        if self.pretty {
+            self.out.push('\n');
+            self.out.push_str(&"  ".repeat(self.indent));
+        } else {
+            self.out.push(' ');
+        }
+    }
+
+    // 括号中的内容，多行格式时单独缩进一层
+    fn block(&mut self, write: impl FnOnce(&mut Self)) {
+        self.push("(");
+        if self.pretty {
+            self.indent += 1;
+            self.separator();
+            write(self);
+            self.indent -= 1;
+            self.separator();
+        } else {
+            write(self);
+        }
+        self.push(")");
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Select(select) => self.select(select),
+            Statement::Insert(insert) => self.insert(insert),
+            Statement::Update(update) => {
+                self.push(&format!("UPDATE {}", identifier(&update.table)));
+                self.separator();
+                self.push("SET ");
+                for (i, (column, expr)) in update.assignments.iter().enumerate() {
+                    if i > 0 {
+                        self.push(", ");
+                    }
+                    self.push(&format!("{} = ", identifier(column)));
+                    self.expr(expr, 0);
+                }
+                self.where_clause(&update.selection);
+            }
+            Statement::Delete(delete) => {
+                self.push(&format!("DELETE FROM {}", identifier(&delete.table)));
+                self.where_clause(&delete.selection);
+            }
+            Statement::CreateTable(create) => {
+                self.push(&format!("CREATE TABLE {} ", identifier(&create.table)));
+                let mut items: Vec<String> = create.fields.iter().map(field).collect();
+                if let Some(key) = &create.primary_key {
+                    items.push(format!("PRIMARY KEY ({})", identifier(key)));
+                }
+                self.block(|writer| {
+                    for (i, item) in items.iter().enumerate() {
+                        if i > 0 {
+                            writer.push(",");
+                            writer.separator();
+                        }
+                        writer.push(item);
+                    }
+                });
+            }
+            Statement::DropTable(drop) => {
+                self.push("DROP TABLE ");
+                if drop.if_exists {
+                    self.push("IF EXISTS ");
+                }
+                self.push(&identifier(&drop.table));
+            }
+            Statement::Truncate(table) => {
+                self.push(&format!("TRUNCATE TABLE {}", identifier(table)));
+            }
+            Statement::AlterTable(alter) => {
+                self.push(&format!("ALTER TABLE {} ", identifier(&alter.table)));
+                let operation = match &alter.operation {
+                    AlterOperation::AddColumn(f) => format!("ADD COLUMN {}", field(f)),
+                    AlterOperation::DropColumn(name) => {
+                        format!("DROP COLUMN {}", identifier(name))
+                    }
+                    AlterOperation::RenameColumn { from, to } => {
+                        format!("RENAME COLUMN {} TO {}", identifier(from), identifier(to))
+                    }
+                    AlterOperation::RenameTable(name) => format!("RENAME TO {}", identifier(name)),
+                };
+                self.push(&operation);
+            }
+            Statement::CreateDatabase {
+                name,
+                if_not_exists,
+            } => {
+                self.push("CREATE DATABASE ");
+                if *if_not_exists {
+                    self.push("IF NOT EXISTS ");
+                }
+                self.push(&identifier(name));
+            }
+            Statement::DropDatabase { name, if_exists } => {
+                self.push("DROP DATABASE ");
+                if *if_exists {
+                    self.push("IF EXISTS ");
+                }
+                self.push(&identifier(name));
+            }
+            Statement::Use(name) => self.push(&format!("USE {}", identifier(name))),
+            Statement::CreateIndex(create) => {
+                self.push(&format!(
+                    "CREATE {}INDEX {} ON {} {}",
+                    if create.unique { "UNIQUE " } else { "" },
+                    identifier(&create.name),
+                    identifier(&create.table),
+                    identifier_list(&create.columns)
+                ));
+            }
+            Statement::DropIndex { name, table } => {
+                self.push(&format!(
+                    "DROP INDEX {} ON {}",
+                    identifier(name),
+                    identifier(table)
+                ));
+            }
+        }
+    }
+
+    fn insert(&mut self, insert: &Insert) {
+        self.push(&format!("INSERT INTO {}", identifier(&insert.table)));
+        if !insert.columns.is_empty() {
+            self.push(&format!(" {}", identifier_list(&insert.columns)));
+        }
+        self.separator();
+        match &insert.source {
+            InsertSource::Select(select) => self.select(select),
+            InsertSource::Values(rows) => {
+                self.push("VALUES");
+                // 多行时每行单独一行
+                self.indent += 1;
+                for (i, row) in rows.iter().enumerate() {
+                    if i > 0 {
+                        self.push(",");
+                    }
+                    if rows.len() > 1 {
+                        self.separator();
+                    } else {
+                        self.push(" ");
+                    }
+                    self.push("(");
+                    self.expr_list(row);
+                    self.push(")");
+                }
+                self.indent -= 1;
+            }
+        }
+    }
+
+    fn select(&mut self, select: &Select) {
+        self.push("SELECT ");
+        for (i, item) in select.items.iter().enumerate() {
+            if i > 0 {
+                self.push(", ");
+            }
+            match item {
+                SelectItem::Wildcard => self.push("*"),
+                SelectItem::Expr { expr, alias } => {
+                    self.expr(expr, 0);
+                    if let Some(alias) = alias {
+                        self.push(&format!(" AS {}", identifier(alias)));
+                    }
+                }
+            }
+        }
+        if let Some(from) = &select.from {
+            self.separator();
+            self.push("FROM ");
+            self.table_ref(from);
+        }
+        for join in select.joins.iter() {
+            self.separator();
+            self.push(match join.kind {
+                JoinKind::Inner => "JOIN ",
+                JoinKind::Left => "LEFT JOIN ",
+            });
+            self.table_ref(&join.table);
+            self.push(" ON ");
+            self.expr(&join.on, 0);
+        }
+        self.where_clause(&select.selection);
+        if !select.group_by.is_empty() {
+            self.separator();
+            self.push("GROUP BY ");
+            self.expr_list(&select.group_by);
+        }
+        if let Some(having) = &select.having {
+            self.separator();
+            self.push("HAVING ");
+            self.expr(having, 0);
+        }
+        if !select.order_by.is_empty() {
+            self.separator();
+            self.push("ORDER BY ");
+            for (i, order) in select.order_by.iter().enumerate() {
+                if i > 0 {
+                    self.push(", ");
+                }
+                self.expr(&order.expr, 0);
+                if order.desc {
+                    self.push(" DESC");
+                }
+            }
+        }
+        if let Some(limit) = select.limit {
+            self.separator();
+            self.push(&format!("LIMIT {}", limit));
+        }
+        if let Some(offset) = select.offset {
+            self.separator();
+            self.push(&format!("OFFSET {}", offset));
+        }
+    }
+
+    fn table_ref(&mut self, table: &TableRef) {
+        self.push(&identifier(&table.name));
+        if let Some(alias) = &table.alias {
+            self.push(&format!(" AS {}", identifier(alias)));
+        }
+    }
+
+    fn where_clause(&mut self, selection: &Option<Expr>) {
+        if let Some(expr) = selection {
+            self.separator();
+            self.push("WHERE ");
+            self.expr(expr, 0);
+        }
+    }
+
+    fn subquery(&mut self, select: &Select) {
+        self.block(|writer| writer.select(select));
+    }
+
+    fn expr_list(&mut self, list: &[Expr]) {
+        for (i, expr) in list.iter().enumerate() {
+            if i > 0 {
+                self.push(", ");
+            }
+            self.expr(expr, 0);
+        }
+    }
+
+    // 优先级低于 min 的表达式需要加括号
+    fn expr(&mut self, expr: &Expr, min: u8) {
+        let parenthesized = precedence(expr) < min;
+        if parenthesized {
+            self.push("(");
+        }
+        match expr {
+            Expr::Literal(value) => self.push(&literal(value)),
+            Expr::Placeholder(i) => self.push(&format!("${}", i + 1)),
+            Expr::Column { table, name } => {
+                if let Some(table) = table {
+                    self.push(&format!("{}.", identifier(table)));
+                }
+                self.push(&identifier(name));
+            }
+            Expr::Binary { left, op, right } => {
+                // and、or 左结合，比较运算的两边只能是 primary
+                let (keyword, left_min, right_min) = match op {
+                    BinaryOp::Or => ("OR", 1, 2),
+                    BinaryOp::And => ("AND", 2, 3),
+                    BinaryOp::Eq => ("=", PRIMARY, PRIMARY),
+                    BinaryOp::NotEq => ("<>", PRIMARY, PRIMARY),
+                    BinaryOp::Lt => ("<", PRIMARY, PRIMARY),
+                    BinaryOp::LtEq => ("<=", PRIMARY, PRIMARY),
+                    BinaryOp::Gt => (">", PRIMARY, PRIMARY),
+                    BinaryOp::GtEq => (">=", PRIMARY, PRIMARY),
+                };
+                self.expr(left, left_min);
+                self.push(&format!(" {} ", keyword));
+                self.expr(right, right_min);
+            }
+            Expr::Unary {
+                op: UnaryOp::Not,
+                expr,
+            } => {
+                self.push("NOT ");
+                self.expr(expr, 3);
+            }
+            Expr::Aggregate { func, arg } => {
+                self.push(&format!("{}(", func.name().to_ascii_uppercase()));
+                match arg {
+                    Some(arg) => self.expr(arg, 0),
+                    None => self.push("*"),
+                }
+                self.push(")");
+            }
+            Expr::Subquery(select) => self.subquery(select),
+            Expr::Exists(select) => {
+                self.push("EXISTS ");
+                self.subquery(select);
+            }
+            Expr::InSubquery {
+                expr,
+                subquery,
+                negated,
+            } => {
+                self.expr(expr, PRIMARY);
+                self.push(if *negated { " NOT IN " } else { " IN " });
+                self.subquery(subquery);
+            }
+            Expr::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                self.expr(expr, PRIMARY);
+                self.push(if *negated { " NOT IN (" } else { " IN (" });
+                self.expr_list(list);
+                self.push(")");
+            }
+            Expr::Between {
+                expr,
+                low,
+                high,
+                negated,
+            } => {
+                self.expr(expr, PRIMARY);
+                self.push(if *negated {
+                    " NOT BETWEEN "
+                } else {
+                    " BETWEEN "
+                });
+                self.expr(low, PRIMARY);
+                self.push(" AND ");
+                self.expr(high, PRIMARY);
+            }
+            Expr::Like {
+                expr,
+                pattern,
+                escape,
+                negated,
+            } => {
+                self.expr(expr, PRIMARY);
+                self.push(if *negated { " NOT LIKE " } else { " LIKE " });
+                self.expr(pattern, PRIMARY);
+                if let Some(escape) = escape {
+                    self.push(" ESCAPE ");
+                    self.expr(escape, PRIMARY);
+                }
+            }
+        }
+        if parenthesized {
+            self.push(")");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::parser::Parser;
+    use super::super::token;
+    use super::*;
+
+    fn parse(sql: &str) -> Statement {
+        Parser::new(token::tokenize(sql)).parse().unwrap()
+    }
+
+    // 两种格式重新解析后都应该得到相同的语法树
+    fn round_trip(sql: &str) -> String {
+        let statement = parse(sql);
+        let line = statement.to_string();
+        assert_eq!(parse(&line), statement, "{}", line);
+        let pretty = format!("{:#}", statement);
+        assert_eq!(parse(&pretty), statement, "{}", pretty);
+        line
+    }
+
+    #[test]
+    fn canonical() {
+        assert_eq!(
+            round_trip("select  id,Name as n from User u where id>=1and name!='x' order by id desc limit 5"),
+            "SELECT id, name AS n FROM user AS u WHERE id >= 1 AND name <> 'x' ORDER BY id DESC LIMIT 5"
+        );
+        assert_eq!(
+            round_trip(
+                "insert into `My Table`(`select`, b) values (1, 'it''s'), (-2, \"a\\\\b\\n\")"
+            ),
+            "INSERT INTO `My Table` (`select`, b) VALUES (1, 'it''s'), (-2, 'a\\\\b\\n')"
+        );
+        assert_eq!(
+            round_trip(
+                "select * from t where (a = 1 or b = 2) and not (c = 3 and d = 4) or e in (1, 2)"
+            ),
+            "SELECT * FROM t WHERE (a = 1 OR b = 2) AND NOT (c = 3 AND d = 4) OR e IN (1, 2)"
+        );
+        assert_eq!(
+            round_trip("create table t (id int primary key, name varchar(15) not null)"),
+            "CREATE TABLE t (id INT, name VARCHAR(15) NOT NULL, PRIMARY KEY (id))"
+        );
+        assert_eq!(
+            round_trip("select 1e3, 2.5, null, true"),
+            "SELECT 1e3, 2.5, NULL, TRUE"
+        );
+        assert_eq!(
+            round_trip("delete from t where id = ?"),
+            "DELETE FROM t WHERE id = $1"
+        );
+    }
+
+    #[test]
+    fn pretty() {
+        let statement = parse(
+            "select u.id, count(*) from user u left join t on u.id = t.id \
+             where u.id in (select id from t where id > 1) group by u.id having count(*) > 1",
+        );
+        assert_eq!(
+            format!("{:#}", statement),
+            "SELECT u.id, COUNT(*)\n\
+             FROM user AS u\n\
+             LEFT JOIN t ON u.id = t.id\n\
+             WHERE u.id IN (\n\
+             \x20 SELECT id\n\
+             \x20 FROM t\n\
+             \x20 WHERE id > 1\n\
+             )\n\
+             GROUP BY u.id\n\
+             HAVING COUNT(*) > 1"
+        );
+        assert_eq!(
+            format!("{:#}", parse("insert into t values (1, 'a'), (2, 'b')")),
+            "INSERT INTO t\nVALUES\n  (1, 'a'),\n  (2, 'b')"
+        );
+        assert_eq!(
+            format!("{:#}", parse("create table t (id int, primary key (id))")),
+            "CREATE TABLE t (\n  id INT,\n  PRIMARY KEY (id)\n)"
+        );
+    }
+
+    #[test]
+    fn statements() {
+        for sql in [
+            "select count(*) as c, sum(a), max(b) from t join s on t.id = s.id group by a",
+            "select * from t where not not a = 1 and b not between 1 and 10",
+            "select * from t where name not like 'a!%%' escape '!' and exists (select * from s)",
+            "select a from t where a not in (select a from s where s.b = t.b) offset 3",
+            "select (select max(a) from s) from t where (a = 1) = true",
+            "insert into t (a) select b from s where b = 1",
+            "update t set a = 1, b = 'x' where a = $2 and b = $1",
+            "drop table if exists t",
+            "truncate t",
+            "alter table t add column c char(3) not null",
+            "alter table t drop c",
+            "alter table t rename column a to `B`",
+            "alter table t rename to s",
+            "create database if not exists shop",
+            "drop database shop",
+            "use shop",
+            "create unique index ab on t (a, b)",
+            "drop index ab on t",
+            "create table e (d date, t time not null, dt datetime, ts timestamp)",
+            "select * from e where d between date '2024-01-01' and '2024-12-31' and t < time '12:30'",
+            "select timestamp '2024-02-29T21:45:30.25+08:00', datetime '2024-02-29'",
+            "create table p (a decimal, b decimal(5), c decimal(38, 10) not null)",
+            "select * from p where a > decimal '-12.50' and b = 1.5",
+            "create table n (a tinyint, b smallint unsigned, c integer not null, d bigint unsigned)",
+            "create table f (a float, b double not null, c bool)",
+            "select * from f where a > .5 and b < -1.5e-300 and c = true",
+            "create table b (body text not null, data blob)",
+            "insert into b values ('abc', x'00fF'), ('', X'')",
+        ] {
+            round_trip(sql);
+        }
+    }
+}
@@ -1,484 +1,903 @@
+use super::ast::*;
+use super::error::ParseError;
 use super::token::{Token, TokenType};
+use crate::core::decimal::{Decimal, MAX_PRECISION};
+use crate::core::temporal::{convert, is_temporal};
+use crate::core::value::Value;
 
-use std::fs::File;
-use std::io::{BufReader, Read, Write};
-
-use serde_derive::{Deserialize, Serialize};
-
-#[derive(Clone, Copy)]
-pub enum ParseState {
-    Begin,
-    InSelect,
-    InFrom,
-    InWhere,
-    InInsert,
-    InInto,
-    InValues,
-    InUpdate,
-    InDelete,
-    InSet,
-    InOrder,
-    InBoundary,
-    End,
-
-    InCreate,
-    InTable,
-    InFields,
-    InField,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct Ope {
-    key: Option<String>,
-    operation: Option<String>,
-    value: Option<String>,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct Field {
-    pub value: String,
-    pub fieldtype: String,
-    pub bitsize: usize,
-    pub can_null: bool,
-}
+type Result<T> = std::result::Result<T, ParseError>;
 
-#[derive(Debug, Deserialize, Serialize)]
+// 递归下降的语法分析器，一次解析一条语句
 pub struct Parser {
-    pub method: String,
-    pub table: String,
-    pub pfields: Vec<String>,
-    pub pvalues: Vec<String>,
-    pub pwhere: Vec<Ope>,
-    pub ptable: Vec<Field>,
+    token_stream: Vec<Token>,
+    pos: usize,
+    // 当前语句中 ? 的个数，以及是否用过 $n，两种占位符不能混用
+    anonymous: usize,
+    numbered: bool,
 }
 
-impl Default for Parser {
-    fn default() -> Self {
-        Self {
-            method: Default::default(),
-            table: Default::default(),
-            pfields: Default::default(),
-            pvalues: Default::default(),
-            pwhere: Default::default(),
-            ptable: Default::default(),
+impl Parser {
+    pub fn new(token_stream: Vec<Token>) -> Self {
+        Parser {
+            token_stream,
+            pos: 0,
+            anonymous: 0,
+            numbered: false,
         }
     }
-}
 
-impl Default for Ope {
-    fn default() -> Self {
-        Self {
-            key: Default::default(),
-            operation: Default::default(),
-            value: Default::default(),
+    pub fn parse(&mut self) -> Result<Statement> {
+        let statement = self.statement()?;
+        self.eat_boundary(";");
+        if self.peek().is_some() {
+            return Err(self.error("end of statement"));
         }
+        Ok(statement)
     }
-}
 
-impl Parser {
-    pub fn new() -> Self {
-        Self::default()
+    // 解析用 ; 分隔的多条语句，空语句会被跳过
+    pub fn parse_script(&mut self) -> Result<Vec<Statement>> {
+        let mut statements = vec![];
+        loop {
+            while self.eat_boundary(";") {}
+            if self.peek().is_none() {
+                return Ok(statements);
+            }
+            statements.push(self.statement()?);
+            if self.peek().is_some() && !self.eat_boundary(";") {
+                return Err(self.error("`;`"));
+            }
+        }
     }
 
-    pub fn parse(&mut self, token_stream: Vec<Token>) -> &mut Self {
-        match &token_stream[0].tokentype {
-            TokenType::KeyWord => match token_stream[0].value.as_str() {
-                "select" => {
-                    return Self::select(self, token_stream);
-                }
-                "insert" => {
-                    return Self::insert(self, token_stream);
-                }
-                "update" => {
-                    return Self::update(self, token_stream);
+    fn statement(&mut self) -> Result<Statement> {
+        self.anonymous = 0;
+        self.numbered = false;
+        let statement = match self.peek() {
+            Some(token) if token.tokentype == TokenType::KeyWord => match token.value.as_str() {
+                "select" => Statement::Select(self.select()?),
+                "insert" => Statement::Insert(self.insert()?),
+                "update" => Statement::Update(self.update()?),
+                "delete" => Statement::Delete(self.delete()?),
+                "create" if self.is_at(1, TokenType::KeyWord, "database") => {
+                    self.create_database()?
                 }
-                "delete" => {
-                    return Self::delete(self, token_stream);
+                "drop" if self.is_at(1, TokenType::KeyWord, "database") => self.drop_database()?,
+                "create"
+                    if self.is_at(1, TokenType::KeyWord, "index")
+                        || self.is_at(1, TokenType::KeyWord, "unique") =>
+                {
+                    Statement::CreateIndex(self.create_index()?)
                 }
-                "create" => {
-                    return Self::create_table(self, token_stream);
+                "drop" if self.is_at(1, TokenType::KeyWord, "index") => self.drop_index()?,
+                "use" => {
+                    self.pos += 1;
+                    Statement::Use(self.identifier()?)
                 }
-                _ => {}
+                "create" => Statement::CreateTable(self.create_table()?),
+                "drop" => Statement::DropTable(self.drop_table()?),
+                "truncate" => Statement::Truncate(self.truncate()?),
+                "alter" => Statement::AlterTable(self.alter_table()?),
+                _ => return Err(self.error("statement")),
             },
-            _ => {}
+            _ => return Err(self.error("statement")),
+        };
+        Ok(statement)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.token_stream.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.token_stream.get(self.pos).cloned();
+        if token.is_some() {
+            self.pos += 1;
         }
-        self
+        token
     }
 
-    pub fn create_table(&mut self, token_stream: Vec<Token>) -> &mut Self {
-        let mut parsestate = ParseState::Begin;
+    // 在当前 token 处出错，到了末尾时指向最后一个 token 之后
+    fn error(&self, expected: &str) -> ParseError {
+        match self.peek() {
+            Some(token) => ParseError {
+                expected: expected.to_string(),
+                found: Some(token.value.clone()),
+                offset: token.offset,
+                length: token.length,
+            },
+            None => ParseError {
+                expected: expected.to_string(),
+                found: None,
+                offset: self
+                    .token_stream
+                    .last()
+                    .map_or(0, |token| token.offset + token.length),
+                length: 0,
+            },
+        }
+    }
 
-        for (_, token) in token_stream.iter().enumerate() {
-            let value = token.value.as_str();
-            let tokentype = &token.tokentype;
-            match tokentype {
-                TokenType::KeyWord => match value {
-                    "create" => {
-                        self.method = "create".to_string();
-                        parsestate = ParseState::InCreate;
-                    }
-                    "table" => {
-                        parsestate = ParseState::InTable;
-                    }
-                    "null" => {
-                        self.ptable.last_mut().unwrap().can_null = false;
-                    }
-                    _ => {}
-                },
-                TokenType::String => match parsestate {
-                    ParseState::InTable => {
-                        self.table = value.to_string();
-                    }
-                    ParseState::InFields => {
-                        self.ptable.push(Field {
-                            value: value.to_string(),
-                            fieldtype: String::new(),
-                            bitsize: 0,
-                            can_null: true,
-                        });
-                    }
-                    ParseState::InField => {
-                        println!("{}", value);
-                        self.ptable.last_mut().unwrap().bitsize = value.parse().unwrap();
-                    }
-                    _ => {}
-                },
-                &TokenType::ELEMTYPE => match value {
-                    "int" => {
-                        self.ptable.last_mut().unwrap().fieldtype = "int".to_string();
-                        self.ptable.last_mut().unwrap().bitsize = 8;
-                    }
-                    "varchar" => {
-                        self.ptable.last_mut().unwrap().fieldtype = "varchar".to_string();
-                    }
-                    "char" => {
-                        self.ptable.last_mut().unwrap().fieldtype = "char".to_string();
-                    }
-                    _ => {}
-                },
-                &TokenType::Boundary => match parsestate {
-                    ParseState::InTable => match value {
-                        "(" => {
-                            parsestate = ParseState::InFields;
-                        }
-                        _ => {}
-                    },
-                    ParseState::InFields => match value {
-                        "(" => {
-                            parsestate = ParseState::InField;
-                        }
-                        "," => {
-                            parsestate = ParseState::InFields;
-                        }
-                        _ => {}
-                    },
-                    ParseState::InField => match value {
-                        ")" => {
-                            parsestate = ParseState::InFields;
-                        }
-                        _ => {}
-                    },
-                    _ => {}
-                },
+    fn is(&self, tokentype: TokenType, value: &str) -> bool {
+        self.is_at(0, tokentype, value)
+    }
+
+    // 向后看第 n 个 token
+    fn is_at(&self, n: usize, tokentype: TokenType, value: &str) -> bool {
+        match self.token_stream.get(self.pos + n) {
+            Some(token) => token.tokentype == tokentype && token.value == value,
+            None => false,
+        }
+    }
+
+    fn eat(&mut self, tokentype: TokenType, value: &str) -> bool {
+        if self.is(tokentype, value) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        self.eat(TokenType::KeyWord, keyword)
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        if !self.eat_keyword(keyword) {
+            return Err(self.error(&format!("`{}`", keyword)));
+        }
+        Ok(())
+    }
+
+    fn eat_boundary(&mut self, boundary: &str) -> bool {
+        self.eat(TokenType::Boundary, boundary)
+    }
 
-                _ => {}
+    fn expect_boundary(&mut self, boundary: &str) -> Result<()> {
+        if !self.eat_boundary(boundary) {
+            return Err(self.error(&format!("`{}`", boundary)));
+        }
+        Ok(())
+    }
+
+    fn identifier(&mut self) -> Result<String> {
+        match self.peek() {
+            Some(token) if token.tokentype == TokenType::Identifier => {
+                Ok(self.next().unwrap().value)
             }
+            _ => Err(self.error("identifier")),
         }
+    }
 
-        self
+    // ( a, b, c )
+    fn identifier_list(&mut self) -> Result<Vec<String>> {
+        self.expect_boundary("(")?;
+        let mut list = vec![self.identifier()?];
+        while self.eat_boundary(",") {
+            list.push(self.identifier()?);
+        }
+        self.expect_boundary(")")?;
+        Ok(list)
     }
 
-    pub fn select(&mut self, token_stream: Vec<Token>) -> &mut Self {
-        let mut parsestate = ParseState::Begin;
-        for (i, token) in token_stream.iter().enumerate() {
-            let value = token.value.as_str();
-            let tokentype = &token.tokentype;
-            match tokentype {
-                TokenType::KeyWord => match value {
-                    "select" => {
-                        self.method = "select".to_string();
-                        parsestate = ParseState::InSelect;
-                    }
-                    "from" => {
-                        parsestate = ParseState::InFrom;
-                    }
-                    "where" => {
-                        parsestate = ParseState::InWhere;
-                    }
-                    _ => {}
-                },
-                TokenType::String => match parsestate {
-                    ParseState::InFrom => {
-                        self.table = value.to_string();
-                    }
-                    ParseState::InSelect => {
-                        self.pfields.push(value.to_string());
+    fn where_clause(&mut self) -> Result<Option<Expr>> {
+        if self.eat_keyword("where") {
+            Ok(Some(self.expr()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // create table user ( id int, name varchar(15) not null )
+    fn create_table(&mut self) -> Result<CreateTable> {
+        self.expect_keyword("create")?;
+        self.expect_keyword("table")?;
+        let table = self.identifier()?;
+        self.expect_boundary("(")?;
+        let mut fields = vec![];
+        let mut primary_key = None;
+        loop {
+            // 主键可以写在字段后面，也可以单独写成 primary key (col)，只能有一个主键
+            if self.is(TokenType::KeyWord, "primary") {
+                if primary_key.is_some() {
+                    return Err(self.error("a single primary key"));
+                }
+                self.pos += 1;
+                self.expect_keyword("key")?;
+                self.expect_boundary("(")?;
+                primary_key = Some(self.identifier()?);
+                self.expect_boundary(")")?;
+            } else {
+                let field = self.field()?;
+                if self.is(TokenType::KeyWord, "primary") {
+                    if primary_key.is_some() {
+                        return Err(self.error("a single primary key"));
                     }
+                    self.pos += 1;
+                    self.expect_keyword("key")?;
+                    primary_key = Some(field.value.clone());
+                }
+                fields.push(field);
+            }
+            if !self.eat_boundary(",") {
+                break;
+            }
+        }
+        self.expect_boundary(")")?;
+        Ok(CreateTable {
+            table,
+            fields,
+            primary_key,
+        })
+    }
 
-                    _ => {}
-                },
-                TokenType::Operation => match value {
-                    "*" => match parsestate {
-                        ParseState::InSelect => {
-                            // push all
-                        }
-                        _ => {}
-                    },
-                    "=" => match parsestate {
-                        ParseState::InWhere => {
-                            if let (Some(k), Some(v)) =
-                                (token_stream.get(i - 1), token_stream.get(i + 1))
-                            {
-                                self.pwhere.push(Ope {
-                                    operation: Some("=".to_string()),
-                                    key: Some(k.value.clone()),
-                                    value: Some(v.value.clone()),
-                                });
-                            }
+    // create database [if not exists] db
+    fn create_database(&mut self) -> Result<Statement> {
+        self.expect_keyword("create")?;
+        self.expect_keyword("database")?;
+        let if_not_exists = self.eat_keyword("if");
+        if if_not_exists {
+            self.expect_keyword("not")?;
+            self.expect_keyword("exists")?;
+        }
+        let name = self.identifier()?;
+        Ok(Statement::CreateDatabase {
+            name,
+            if_not_exists,
+        })
+    }
+
+    // drop database [if exists] db
+    fn drop_database(&mut self) -> Result<Statement> {
+        self.expect_keyword("drop")?;
+        self.expect_keyword("database")?;
+        let if_exists = self.eat_keyword("if");
+        if if_exists {
+            self.expect_keyword("exists")?;
+        }
+        let name = self.identifier()?;
+        Ok(Statement::DropDatabase { name, if_exists })
+    }
+
+    // create [unique] index name on t (col, ...)
+    fn create_index(&mut self) -> Result<CreateIndex> {
+        self.expect_keyword("create")?;
+        let unique = self.eat_keyword("unique");
+        self.expect_keyword("index")?;
+        let name = self.identifier()?;
+        self.expect_keyword("on")?;
+        let table = self.identifier()?;
+        let columns = self.identifier_list()?;
+        Ok(CreateIndex {
+            name,
+            table,
+            columns,
+            unique,
+        })
+    }
+
+    // drop index name on t
+    fn drop_index(&mut self) -> Result<Statement> {
+        self.expect_keyword("drop")?;
+        self.expect_keyword("index")?;
+        let name = self.identifier()?;
+        self.expect_keyword("on")?;
+        let table = self.identifier()?;
+        Ok(Statement::DropIndex { name, table })
+    }
+
+    // drop table [if exists] t
+    fn drop_table(&mut self) -> Result<DropTable> {
+        self.expect_keyword("drop")?;
+        self.expect_keyword("table")?;
+        let if_exists = self.eat_keyword("if");
+        if if_exists {
+            self.expect_keyword("exists")?;
+        }
+        let table = self.identifier()?;
+        Ok(DropTable { table, if_exists })
+    }
+
+    // truncate [table] t
+    fn truncate(&mut self) -> Result<String> {
+        self.expect_keyword("truncate")?;
+        self.eat_keyword("table");
+        self.identifier()
+    }
+
+    // alter table t add [column] name type | drop [column] name
+    //             | rename column a to b | rename [to] name
+    fn alter_table(&mut self) -> Result<AlterTable> {
+        self.expect_keyword("alter")?;
+        self.expect_keyword("table")?;
+        let table = self.identifier()?;
+        let operation = if self.eat_keyword("add") {
+            self.eat_keyword("column");
+            AlterOperation::AddColumn(self.field()?)
+        } else if self.eat_keyword("drop") {
+            self.eat_keyword("column");
+            AlterOperation::DropColumn(self.identifier()?)
+        } else if self.eat_keyword("rename") {
+            if self.eat_keyword("column") {
+                let from = self.identifier()?;
+                self.expect_keyword("to")?;
+                let to = self.identifier()?;
+                AlterOperation::RenameColumn { from, to }
+            } else {
+                self.eat_keyword("to");
+                AlterOperation::RenameTable(self.identifier()?)
+            }
+        } else {
+            return Err(self.error("`add`, `drop` or `rename`"));
+        };
+        Ok(AlterTable { table, operation })
+    }
+
+    fn field(&mut self) -> Result<Field> {
+        let value = self.identifier()?;
+        let fieldtype = match self.peek() {
+            Some(token) if token.tokentype == TokenType::ELEMTYPE => self.next().unwrap().value,
+            _ => return Err(self.error("column type")),
+        };
+        // integer 是 int 的别名，bool 是 boolean 的别名
+        let fieldtype = match fieldtype.as_str() {
+            "integer" => "int".to_string(),
+            "bool" => "boolean".to_string(),
+            _ => fieldtype,
+        };
+        let mut scale = 0;
+        let bitsize = match fieldtype.as_str() {
+            // 整数类型按实际的字节数保存
+            "tinyint" => 1,
+            "smallint" => 2,
+            "int" => 4,
+            "bigint" => 8,
+            // float 是单精度，double 是双精度的 IEEE 754 浮点数
+            "float" => 4,
+            "double" => 8,
+            "boolean" => 1,
+            // 值保存在溢出页中，行中只有第一个溢出页的位置和值的长度
+            "text" | "blob" => 16,
+            // 日期和时间都是定长的
+            "date" => 4,
+            "time" | "datetime" | "timestamp" => 8,
+            // decimal(p, s)，省略时为 decimal(10, 0)
+            "decimal" => {
+                let (precision, s) = self.decimal_size()?;
+                scale = s;
+                precision
+            }
+            "char" | "varchar" => {
+                self.expect_boundary("(")?;
+                // 变长字段的长度只用一个字节记录
+                let bitsize = match self.peek() {
+                    Some(token) if token.tokentype == TokenType::Integer => {
+                        match token.value.parse::<usize>() {
+                            Ok(n) if (1..=255).contains(&n) => n,
+                            _ => return Err(self.error("length between 1 and 255")),
                         }
+                    }
+                    _ => return Err(self.error("length")),
+                };
+                self.pos += 1;
+                self.expect_boundary(")")?;
+                bitsize
+            }
+            _ => {
+                self.pos -= 1;
+                return Err(self.error("column type"));
+            }
+        };
+        let unsigned = ["tinyint", "smallint", "int", "bigint"].contains(&fieldtype.as_str())
+            && self.eat_keyword("unsigned");
+        let mut can_null = true;
+        if self.eat_keyword("not") {
+            if !self.eat(TokenType::Null, "null") {
+                return Err(self.error("`null`"));
+            }
+            can_null = false;
+        } else {
+            self.eat(TokenType::Null, "null");
+        }
+        Ok(Field {
+            value,
+            fieldtype,
+            bitsize,
+            can_null,
+            scale,
+            unsigned,
+        })
+    }
 
-                        _ => {}
-                    },
-                    _ => {}
-                },
-                _ => {}
+    // (p[, s])，1 <= p <= 38，0 <= s <= p
+    fn decimal_size(&mut self) -> Result<(usize, usize)> {
+        if !self.eat_boundary("(") {
+            return Ok((10, 0));
+        }
+        let precision = self.size(1, MAX_PRECISION, "precision between 1 and 38")?;
+        let scale = if self.eat_boundary(",") {
+            self.size(0, precision, &format!("scale between 0 and {}", precision))?
+        } else {
+            0
+        };
+        self.expect_boundary(")")?;
+        Ok((precision, scale))
+    }
+
+    fn size(&mut self, min: usize, max: usize, expected: &str) -> Result<usize> {
+        let size = match self.peek() {
+            Some(token) if token.tokentype == TokenType::Integer => token.value.parse().ok(),
+            _ => None,
+        };
+        match size {
+            Some(size) if (min..=max).contains(&size) => {
+                self.pos += 1;
+                Ok(size)
             }
+            _ => Err(self.error(expected)),
         }
-        self
     }
 
-    pub fn insert(&mut self, token_stream: Vec<Token>) -> &mut Self {
-        let mut parsestate = ParseState::Begin;
-        for (i, token) in token_stream.iter().enumerate() {
-            let value = token.value.as_str();
-            let tokentype = &token.tokentype;
-            match tokentype {
-                TokenType::KeyWord => match value {
-                    "insert" => {
-                        self.method = "insert".to_string();
-                        parsestate = ParseState::InInsert;
-                    }
-                    "values" => {
-                        parsestate = ParseState::InValues;
-                    }
-                    _ => {}
-                },
-                TokenType::String => match parsestate {
-                    ParseState::InInsert => {
-                        self.table = value.to_string();
-                    }
-                    _ => {}
-                },
-                TokenType::Boundary => match value {
-                    "(" => match parsestate {
-                        ParseState::InInsert => {
-                            parsestate = ParseState::InInto;
-                            let mut mark = i;
-                            loop {
-                                if let Some(t) = token_stream.get(mark) {
-                                    match t.tokentype {
-                                        TokenType::String => {
-                                            self.pfields.push(t.value.to_string());
-                                        }
-                                        TokenType::Boundary => {
-                                            if t.value.as_str() == ")" {
-                                                break;
-                                            }
-                                        }
-                                        _ => {}
-                                    }
-                                    mark += 1;
-                                }
-                            }
-                        }
-                        ParseState::InValues => {
-                            let mut mark = i;
-                            loop {
-                                if let Some(t) = token_stream.get(mark) {
-                                    match t.tokentype {
-                                        TokenType::String => {
-                                            self.pvalues.push(t.value.to_string());
-                                        }
-                                        TokenType::Boundary => {
-                                            if t.value.as_str() == ")" {
-                                                break;
-                                            }
-                                        }
-                                        _ => {}
-                                    }
-                                    mark += 1;
-                                }
-                            }
-                        }
-                        _ => {}
-                    },
-                    _ => {}
-                },
-                TokenType::Number => {}
-                _ => {}
+    // select * from user where id = 1
+    fn select(&mut self) -> Result<Select> {
+        self.expect_keyword("select")?;
+        let mut items = vec![];
+        loop {
+            if self.eat(TokenType::Operation, "*") {
+                items.push(SelectItem::Wildcard);
+            } else {
+                let expr = self.expr()?;
+                let alias = if self.eat_keyword("as") {
+                    Some(self.identifier()?)
+                } else {
+                    None
+                };
+                items.push(SelectItem::Expr { expr, alias });
+            }
+            if !self.eat_boundary(",") {
+                break;
             }
         }
+        let mut joins = vec![];
+        let from = if self.eat_keyword("from") {
+            let from = self.table_ref()?;
+            while let Some(join) = self.join()? {
+                joins.push(join);
+            }
+            Some(from)
+        } else {
+            None
+        };
+        let selection = self.where_clause()?;
+        let mut group_by = vec![];
+        if self.eat_keyword("group") {
+            self.expect_keyword("by")?;
+            group_by.push(self.expr()?);
+            while self.eat_boundary(",") {
+                group_by.push(self.expr()?);
+            }
+        }
+        let having = if self.eat_keyword("having") {
+            Some(self.expr()?)
+        } else {
+            None
+        };
+        let mut order_by = vec![];
+        if self.eat_keyword("order") {
+            self.expect_keyword("by")?;
+            loop {
+                let expr = self.expr()?;
+                let desc = if self.eat_keyword("desc") {
+                    true
+                } else {
+                    self.eat_keyword("asc");
+                    false
+                };
+                order_by.push(OrderBy { expr, desc });
+                if !self.eat_boundary(",") {
+                    break;
+                }
+            }
+        }
+        let limit = if self.eat_keyword("limit") {
+            Some(self.count()?)
+        } else {
+            None
+        };
+        let offset = if self.eat_keyword("offset") {
+            Some(self.count()?)
+        } else {
+            None
+        };
+        Ok(Select {
+            items,
+            from,
+            joins,
+            selection,
+            group_by,
+            having,
+            order_by,
+            limit,
+            offset,
+        })
+    }
 
-        self
+    // [inner] join t on expr | left [outer] join t on expr
+    fn join(&mut self) -> Result<Option<Join>> {
+        let kind = if self.eat_keyword("join") {
+            JoinKind::Inner
+        } else if self.eat_keyword("inner") {
+            self.expect_keyword("join")?;
+            JoinKind::Inner
+        } else if self.eat_keyword("left") {
+            self.eat_keyword("outer");
+            self.expect_keyword("join")?;
+            JoinKind::Left
+        } else {
+            return Ok(None);
+        };
+        let table = self.table_ref()?;
+        self.expect_keyword("on")?;
+        let on = self.expr()?;
+        Ok(Some(Join { kind, table, on }))
     }
 
-    pub fn update(&mut self, token_stream: Vec<Token>) -> &mut Self {
-        let mut parsestate = ParseState::Begin;
-        for (i, token) in token_stream.iter().enumerate() {
-            let value = token.value.as_str();
-            let tokentype = &token.tokentype;
-            match tokentype {
-                TokenType::KeyWord => match value {
-                    "update" => {
-                        self.method = "update".to_string();
-                        parsestate = ParseState::InUpdate;
-                    }
-                    "where" => {
-                        parsestate = ParseState::InWhere;
-                    }
-                    "set" => {
-                        parsestate = ParseState::InSet;
-                    }
-                    _ => {}
-                },
-                TokenType::String => match parsestate {
-                    ParseState::InUpdate => {
-                        self.table = value.to_string();
-                    }
-                    _ => {}
-                },
-                TokenType::Operation => match value {
-                    "=" => match parsestate {
-                        ParseState::InSet => match tokentype {
-                            TokenType::Operation => {
-                                if value == "=" {
-                                    if let (Some(k), Some(v)) =
-                                        (token_stream.get(i - 1), token_stream.get(i + 1))
-                                    {
-                                        self.pfields.push(k.value.to_string());
-                                        self.pvalues.push(v.value.to_string());
-                                    }
-                                }
-                            }
-                            _ => {}
-                        },
-                        ParseState::InWhere => {
-                            if let (Some(k), Some(v)) =
-                                (token_stream.get(i - 1), token_stream.get(i + 1))
-                            {
-                                self.pwhere.push(Ope {
-                                    operation: Some("=".to_string()),
-                                    key: Some(k.value.clone()),
-                                    value: Some(v.value.clone()),
-                                });
-                            }
-                        }
-                        _ => {}
-                    },
+    // limit 和 offset 后面的非负整数
+    fn count(&mut self) -> Result<usize> {
+        let count = match self.peek() {
+            Some(token) if token.tokentype == TokenType::Integer => token.value.parse().ok(),
+            _ => None,
+        };
+        match count {
+            Some(count) => {
+                self.pos += 1;
+                Ok(count)
+            }
+            None => Err(self.error("non-negative integer")),
+        }
+    }
 
-                    _ => {}
-                },
-                TokenType::Number => {}
-                _ => {}
+    // user [as] u
+    fn table_ref(&mut self) -> Result<TableRef> {
+        let name = self.identifier()?;
+        let alias = if self.eat_keyword("as") {
+            Some(self.identifier()?)
+        } else if let Some(Token {
+            tokentype: TokenType::Identifier,
+            ..
+        }) = self.peek()
+        {
+            Some(self.identifier()?)
+        } else {
+            None
+        };
+        Ok(TableRef { name, alias })
+    }
+
+    // insert into user (id, name) values (1, 'a')
+    fn insert(&mut self) -> Result<Insert> {
+        self.expect_keyword("insert")?;
+        self.expect_keyword("into")?;
+        let table = self.identifier()?;
+        let columns = if self.is(TokenType::Boundary, "(") {
+            self.identifier_list()?
+        } else {
+            vec![]
+        };
+        if self.is(TokenType::KeyWord, "select") {
+            let select = self.select()?;
+            return Ok(Insert {
+                table,
+                columns,
+                source: InsertSource::Select(Box::new(select)),
+            });
+        }
+        if !self.eat_keyword("values") {
+            return Err(self.error("`values` or `select`"));
+        }
+        let mut rows = vec![];
+        loop {
+            self.expect_boundary("(")?;
+            let mut values = vec![self.expr()?];
+            while self.eat_boundary(",") {
+                values.push(self.expr()?);
+            }
+            self.expect_boundary(")")?;
+            rows.push(values);
+            if !self.eat_boundary(",") {
+                break;
             }
         }
-        self
+        Ok(Insert {
+            table,
+            columns,
+            source: InsertSource::Values(rows),
+        })
     }
 
-    pub fn delete(&mut self, token_stream: Vec<Token>) -> &mut Self {
-        let mut parsestate = ParseState::Begin;
-        for (i, token) in token_stream.iter().enumerate() {
-            let value = token.value.as_str();
-            let tokentype = &token.tokentype;
-            match tokentype {
-                TokenType::KeyWord => match value {
-                    "delete" => {
-                        self.method = "delete".to_string();
-                        parsestate = ParseState::InDelete;
-                    }
-                    "from" => {
-                        parsestate = ParseState::InFrom;
-                    }
-                    "where" => {
-                        parsestate = ParseState::InWhere;
-                    }
-                    _ => {}
-                },
-                TokenType::String => match parsestate {
-                    ParseState::InFrom => {
-                        self.table = value.to_string();
-                    }
-                    _ => {}
-                },
-                TokenType::Operation => match value {
-                    "*" => match parsestate {
-                        ParseState::InSelect => {}
-                        _ => {}
-                    },
-                    "=" => match parsestate {
-                        ParseState::InWhere => match tokentype {
-                            TokenType::Operation => {
-                                if value == "=" {
-                                    if let (Some(k), Some(v)) =
-                                        (token_stream.get(i - 1), token_stream.get(i + 1))
-                                    {
-                                        self.pwhere.push(Ope {
-                                            operation: Some("=".to_string()),
-                                            key: Some(k.value.clone()),
-                                            value: Some(v.value.clone()),
-                                        });
-                                    }
-                                }
-                            }
-                            _ => {}
-                        },
-                        _ => {}
-                    },
-
-                    _ => {}
-                },
-                TokenType::Number => {}
-                _ => {}
+    // update user set id = 1, name = 'a' where id = 2
+    fn update(&mut self) -> Result<Update> {
+        self.expect_keyword("update")?;
+        let table = self.identifier()?;
+        self.expect_keyword("set")?;
+        let mut assignments = vec![];
+        loop {
+            let column = self.identifier()?;
+            if !self.eat(TokenType::Operation, "=") {
+                return Err(self.error("`=`"));
             }
+            assignments.push((column, self.expr()?));
+            if !self.eat_boundary(",") {
+                break;
+            }
+        }
+        let selection = self.where_clause()?;
+        Ok(Update {
+            table,
+            assignments,
+            selection,
+        })
+    }
+
+    // delete from user where id = 1
+    fn delete(&mut self) -> Result<Delete> {
+        self.expect_keyword("delete")?;
+        self.expect_keyword("from")?;
+        let table = self.identifier()?;
+        let selection = self.where_clause()?;
+        Ok(Delete { table, selection })
+    }
+
+    // 优先级从低到高: or, and, not, 比较运算
+    // expr := and_expr ( or and_expr )*
+    fn expr(&mut self) -> Result<Expr> {
+        let mut left = self.and_expr()?;
+        while self.eat_keyword("or") {
+            let right = self.and_expr()?;
+            left = Expr::binary(left, BinaryOp::Or, right);
         }
+        Ok(left)
+    }
 
-        self
+    // and_expr := not_expr ( and not_expr )*
+    fn and_expr(&mut self) -> Result<Expr> {
+        let mut left = self.not_expr()?;
+        while self.eat_keyword("and") {
+            let right = self.not_expr()?;
+            left = Expr::binary(left, BinaryOp::And, right);
+        }
+        Ok(left)
     }
 
-    pub fn execute(&mut self) {
-        match self.method.as_str() {
-            "insert" => {
+    // not_expr := not not_expr | comparison
+    fn not_expr(&mut self) -> Result<Expr> {
+        if self.eat_keyword("not") {
+            return Ok(Expr::negate(self.not_expr()?));
+        }
+        self.comparison()
+    }
 
-                //
+    // comparison := primary ( op primary
+    //                         | [not] in ( select ) | [not] in ( expr, ... )
+    //                         | [not] between primary and primary
+    //                         | [not] like primary [escape primary] )?
+    fn comparison(&mut self) -> Result<Expr> {
+        let left = self.primary()?;
+        let negated = self.is(TokenType::KeyWord, "not")
+            && matches!(self.token_stream.get(self.pos + 1),
+                Some(token) if token.tokentype == TokenType::KeyWord
+                    && ["in", "between", "like"].contains(&token.value.as_str()));
+        if negated {
+            self.pos += 1;
+        }
+        if self.eat_keyword("in") {
+            self.expect_boundary("(")?;
+            if self.is(TokenType::KeyWord, "select") {
+                let subquery = Box::new(self.select()?);
+                self.expect_boundary(")")?;
+                return Ok(Expr::InSubquery {
+                    expr: Box::new(left),
+                    subquery,
+                    negated,
+                });
             }
-            "create" => {
-                Self::se(self, format!("{}.frm", self.table).as_str());
-                //
+            let mut list = vec![self.expr()?];
+            while self.eat_boundary(",") {
+                list.push(self.expr()?);
             }
-            _ => {}
+            self.expect_boundary(")")?;
+            return Ok(Expr::InList {
+                expr: Box::new(left),
+                list,
+                negated,
+            });
         }
+        // between 的上下界不能直接是 and 表达式，否则和 between 中的 and 冲突
+        if self.eat_keyword("between") {
+            let low = self.primary()?;
+            self.expect_keyword("and")?;
+            let high = self.primary()?;
+            return Ok(Expr::Between {
+                expr: Box::new(left),
+                low: Box::new(low),
+                high: Box::new(high),
+                negated,
+            });
+        }
+        if self.eat_keyword("like") {
+            let pattern = self.primary()?;
+            let escape = if self.eat_keyword("escape") {
+                Some(Box::new(self.primary()?))
+            } else {
+                None
+            };
+            return Ok(Expr::Like {
+                expr: Box::new(left),
+                pattern: Box::new(pattern),
+                escape,
+                negated,
+            });
+        }
+        let op = match self.peek() {
+            Some(token) if token.tokentype == TokenType::Operation => match token.value.as_str() {
+                "=" => BinaryOp::Eq,
+                "!=" | "<>" => BinaryOp::NotEq,
+                "<" => BinaryOp::Lt,
+                "<=" => BinaryOp::LtEq,
+                ">" => BinaryOp::Gt,
+                ">=" => BinaryOp::GtEq,
+                _ => return Ok(left),
+            },
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+        let right = self.primary()?;
+        Ok(Expr::binary(left, op, right))
     }
 
-    pub fn de(name: &str) -> Self {
-        let f = File::open(name).unwrap();
-        let mut s = String::new();
-        let mut reader = BufReader::new(f);
-        reader.read_to_string(&mut s).unwrap();
-        serde_json::from_str::<Self>(&s).unwrap()
+    // primary := 字面量 | 字段 | 聚合函数 | ( expr ) | ( select ) | exists ( select )
+    fn primary(&mut self) -> Result<Expr> {
+        if self.eat_keyword("exists") {
+            self.expect_boundary("(")?;
+            let subquery = self.select()?;
+            self.expect_boundary(")")?;
+            return Ok(Expr::Exists(Box::new(subquery)));
+        }
+        if self.eat_boundary("(") {
+            let expr = if self.is(TokenType::KeyWord, "select") {
+                Expr::Subquery(Box::new(self.select()?))
+            } else {
+                self.expr()?
+            };
+            self.expect_boundary(")")?;
+            return Ok(expr);
+        }
+        if self.is_placeholder() {
+            return self.placeholder();
+        }
+        if self.is_typed_literal() {
+            return self.typed_literal();
+        }
+        let is_call = match self.token_stream.get(self.pos + 1) {
+            Some(token) => token.tokentype == TokenType::Boundary && token.value == "(",
+            None => false,
+        };
+        match self.peek() {
+            Some(token) if token.tokentype == TokenType::Identifier && is_call => self.aggregate(),
+            Some(token) if token.tokentype == TokenType::Identifier => {
+                let name = self.identifier()?;
+                if self.eat_boundary(".") {
+                    Ok(Expr::qualified(&name, &self.identifier()?))
+                } else {
+                    Ok(Expr::column(&name))
+                }
+            }
+            Some(token) => match token.to_value() {
+                Some(value) => {
+                    self.pos += 1;
+                    Ok(Expr::Literal(value))
+                }
+                None => Err(self.error("expression")),
+            },
+            None => Err(self.error("expression")),
+        }
     }
 
-    pub fn se(&self, name: &str) {
-        let sered = serde_json::to_string(self).unwrap();
-        let mut f = File::create(name).unwrap();
-        f.write(sered.as_bytes()).unwrap();
+    // date '2024-01-02'、time '12:00:00'、datetime 和 timestamp 后面跟字符串，
+    // 以及 decimal '12.30'
+    fn is_typed_literal(&self) -> bool {
+        self.peek().is_some_and(|token| {
+            token.tokentype == TokenType::ELEMTYPE
+                && (is_temporal(&token.value) || token.value == "decimal")
+        }) && self
+            .token_stream
+            .get(self.pos + 1)
+            .is_some_and(|token| token.tokentype == TokenType::String)
+    }
+
+    fn typed_literal(&mut self) -> Result<Expr> {
+        let fieldtype = self.next().unwrap().value;
+        let text = self.peek().unwrap().value.clone();
+        let value = if fieldtype == "decimal" {
+            Decimal::parse(&text).map(Value::Decimal)
+        } else {
+            convert(&fieldtype, &Value::Str(text))
+        };
+        match value {
+            Some(value) => {
+                self.pos += 1;
+                Ok(Expr::Literal(value))
+            }
+            None => Err(self.error(&format!("{} literal", fieldtype))),
+        }
+    }
+
+    fn is_placeholder(&self) -> bool {
+        self.peek()
+            .is_some_and(|token| token.tokentype == TokenType::Placeholder)
+    }
+
+    // ? 按出现的顺序编号，$n 是第 n 个参数
+    fn placeholder(&mut self) -> Result<Expr> {
+        let value = self.peek().unwrap().value.clone();
+        let index = if value == "?" {
+            if self.numbered {
+                return Err(self.error("`$n` placeholder"));
+            }
+            self.anonymous += 1;
+            self.anonymous - 1
+        } else {
+            if self.anonymous > 0 {
+                return Err(self.error("`?` placeholder"));
+            }
+            match value[1..].parse::<usize>() {
+                Ok(n) if n > 0 => {
+                    self.numbered = true;
+                    n - 1
+                }
+                _ => return Err(self.error("placeholder number from 1")),
+            }
+        };
+        self.pos += 1;
+        Ok(Expr::Placeholder(index))
+    }
+
+    // count(*) | count(expr) | sum(expr) | avg(expr) | min(expr) | max(expr)
+    fn aggregate(&mut self) -> Result<Expr> {
+        let func = match self
+            .peek()
+            .and_then(|token| AggregateFunc::from_name(&token.value))
+        {
+            Some(func) => func,
+            None => return Err(self.error("count, sum, avg, min or max")),
+        };
+        self.pos += 1;
+        self.expect_boundary("(")?;
+        let arg = if func == AggregateFunc::Count && self.eat(TokenType::Operation, "*") {
+            None
+        } else {
+            Some(Box::new(self.expr()?))
+        };
+        self.expect_boundary(")")?;
+        Ok(Expr::Aggregate { func, arg })
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::core::value::Value;
     use crate::parser::token;
 
+    fn parse(sql: &str) -> Statement {
+        Parser::new(token::tokenize(sql)).parse().unwrap()
+    }
+
+    fn parse_err(sql: &str) -> ParseError {
+        Parser::new(token::tokenize(sql)).parse().unwrap_err()
+    }
+
     #[test]
     fn create_table() {
         let sql = "create table user (
@@ -488,39 +907,683 @@ mod test {
                          col4 varchar(11) ,
                    name varchar(15) not null
    )";
-        // let sql = "   \ninsert into user(id,col2,col3,col4,name)values(1,4,aaaaa,bbbb, cc)where id=1; ";
-        let token_stream = token::trim_to_token_stream(&token::trim_code(sql));
+        let token_stream = token::tokenize(sql);
         println!("{:#?}", token_stream);
-        let mut parser: Parser = Parser::new();
-        parser.parse(token_stream).execute();
-        println!("{:#?}", parser);
+        let statement = Parser::new(token_stream).parse().unwrap();
+        println!("{:#?}", statement);
+        if let Statement::CreateTable(create) = statement {
+            assert_eq!(create.table, "user");
+            assert_eq!(create.fields.len(), 5);
+            assert_eq!(create.fields[2].fieldtype, "char");
+            assert_eq!(create.fields[2].bitsize, 5);
+            assert!(!create.fields[4].can_null);
+        } else {
+            panic!("not a create table statement");
+        }
     }
 
     #[test]
     fn insert_should_ok() {
         let sql = "SELECT id, name from  adwdw where   a   =ad  and b=ad ";
-        // let sql = "   \ninsert into user(id,name)values(1,\"saadwdd\")where id=1; ";
-        // let sql = "delete from user where id = 12";
-        // let sql = "update  user  set          id=1,name=\"acbeix\" where xxx=\"debsxnk\"";
-        let token_stream = token::trim_to_token_stream(&token::trim_code(sql));
-        println!("{:#?}", token_stream);
-        let mut parser: Parser = Parser::new();
-        parser.parse(token_stream).execute();
-        println!("{:#?}", parser);
+        let statement = parse(sql);
+        println!("{:#?}", statement);
+        let expected = Select {
+            items: vec![
+                SelectItem::Expr {
+                    expr: Expr::column("id"),
+                    alias: None,
+                },
+                SelectItem::Expr {
+                    expr: Expr::column("name"),
+                    alias: None,
+                },
+            ],
+            from: Some(TableRef {
+                name: "adwdw".to_string(),
+                alias: None,
+            }),
+            joins: vec![],
+            selection: Some(Expr::binary(
+                Expr::binary(Expr::column("a"), BinaryOp::Eq, Expr::column("ad")),
+                BinaryOp::And,
+                Expr::binary(Expr::column("b"), BinaryOp::Eq, Expr::column("ad")),
+            )),
+            group_by: vec![],
+            having: None,
+            order_by: vec![],
+            limit: None,
+            offset: None,
+        };
+        assert_eq!(statement, Statement::Select(expected));
     }
 
     #[test]
     fn get_fun() {
-        // let sql = "SELECT  * from  adwdw where   a   =ad  and b=ad ";
-        // let sql = "   \ninsert into user(id,name)values(1,\"saadwdd\")where id=1; ";
-        // let sql = "update  user  set          id=1,name=\"acbeix\" where xxx=\"debsxnk\"";
-        // let sql = "delete from user where id = 12";
-        let sql = "   \ninsert into user(id,name)values(1,\"saadwdd\") where id = 1 ";
-        let token_stream = token::trim_to_token_stream(&token::trim_code(sql));
-        println!("{:?}", token_stream);
-        // let mut parser: &Parser = Parser::new().parse(token_stream);
-        // println!("{:#?}", parser);
-
-        // parser.execute();
+        let sql = "   \ninsert into user(id,name)values(1,\"saadwdd\"); ";
+        let statement = parse(sql);
+        println!("{:?}", statement);
+        let expected = Insert {
+            table: "user".to_string(),
+            columns: vec!["id".to_string(), "name".to_string()],
+            source: InsertSource::Values(vec![vec![
+                Expr::Literal(Value::Int(1)),
+                Expr::Literal(Value::Str("saadwdd".to_string())),
+            ]]),
+        };
+        assert_eq!(statement, Statement::Insert(expected));
+    }
+
+    #[test]
+    fn insert_rows() {
+        let statement = parse("insert into user values (1, 'a'), (2, 'b')");
+        let expected = Insert {
+            table: "user".to_string(),
+            columns: vec![],
+            source: InsertSource::Values(vec![
+                vec![
+                    Expr::Literal(Value::Int(1)),
+                    Expr::Literal(Value::Str("a".to_string())),
+                ],
+                vec![
+                    Expr::Literal(Value::Int(2)),
+                    Expr::Literal(Value::Str("b".to_string())),
+                ],
+            ]),
+        };
+        assert_eq!(statement, Statement::Insert(expected));
+        match parse("insert into user (id, name) select id, name from student") {
+            Statement::Insert(Insert {
+                columns,
+                source: InsertSource::Select(select),
+                ..
+            }) => {
+                assert_eq!(columns.len(), 2);
+                assert_eq!(select.from.unwrap().name, "student");
+            }
+            statement => panic!("unexpected statement: {:?}", statement),
+        }
+    }
+
+    #[test]
+    fn primary_key() {
+        let create = |sql| match parse(sql) {
+            Statement::CreateTable(create) => create,
+            statement => panic!("not a create table statement: {:?}", statement),
+        };
+        let table = create("create table t (id int primary key, name varchar(10))");
+        assert_eq!(table.primary_key, Some("id".to_string()));
+        assert_eq!(table.fields.len(), 2);
+        let table = create("create table t (id int, name varchar(10), primary key (name))");
+        assert_eq!(table.primary_key, Some("name".to_string()));
+        assert_eq!(table.fields.len(), 2);
+        assert_eq!(create("create table t (id int)").primary_key, None);
+
+        let err = parse_err("create table t (id int primary key, primary key (id))");
+        assert_eq!(err.expected, "a single primary key");
+        let err = parse_err("create table t (a int, b int, primary key (a, b))");
+        assert_eq!(
+            (err.expected.as_str(), err.found),
+            ("`)`", Some(",".to_string()))
+        );
+    }
+
+    #[test]
+    fn index() {
+        assert_eq!(
+            parse("create unique index idx_name on user (name, age)"),
+            Statement::CreateIndex(CreateIndex {
+                name: "idx_name".to_string(),
+                table: "user".to_string(),
+                columns: vec!["name".to_string(), "age".to_string()],
+                unique: true,
+            })
+        );
+        match parse("create index idx_age on user (age)") {
+            Statement::CreateIndex(index) => assert!(!index.unique),
+            statement => panic!("not a create index statement: {:?}", statement),
+        }
+        assert_eq!(
+            parse("drop index idx_age on user"),
+            Statement::DropIndex {
+                name: "idx_age".to_string(),
+                table: "user".to_string(),
+            }
+        );
+        assert_eq!(
+            parse_err("create unique table t (id int)").expected,
+            "`index`"
+        );
+    }
+
+    #[test]
+    fn placeholder() {
+        let statement = parse("select a from t where a = ? and b in (select c from s where d > ?)");
+        let mut found = vec![];
+        statement
+            .map_placeholders::<()>(&mut |i| {
+                found.push(i);
+                Ok(Expr::Placeholder(i))
+            })
+            .unwrap();
+        assert_eq!(found, vec![0, 1]);
+        assert_eq!(
+            parse("update t set a = $2 where b = $1"),
+            Statement::Update(Update {
+                table: "t".to_string(),
+                assignments: vec![("a".to_string(), Expr::Placeholder(1))],
+                selection: Some(Expr::binary(
+                    Expr::column("b"),
+                    BinaryOp::Eq,
+                    Expr::Placeholder(0)
+                )),
+            })
+        );
+        let bound = parse("insert into t values (?, ?)")
+            .map_placeholders::<()>(&mut |i| Ok(Expr::Literal(Value::Int(i as i64 + 10))))
+            .unwrap();
+        assert_eq!(bound, parse("insert into t values (10, 11)"));
+        assert_eq!(parse_err("select ? , $1").expected, "`?` placeholder");
+        assert_eq!(parse_err("select $1 , ?").expected, "`$n` placeholder");
+        assert_eq!(parse_err("select $0").expected, "placeholder number from 1");
+    }
+
+    #[test]
+    fn temporal() {
+        match parse("create table e (d date, t time not null, dt datetime, ts timestamp)") {
+            Statement::CreateTable(create) => {
+                let types: Vec<(&str, usize)> = create
+                    .fields
+                    .iter()
+                    .map(|field| (field.fieldtype.as_str(), field.bitsize))
+                    .collect();
+                assert_eq!(
+                    types,
+                    vec![("date", 4), ("time", 8), ("datetime", 8), ("timestamp", 8)]
+                );
+            }
+            statement => panic!("not a create table statement: {:?}", statement),
+        }
+        match parse("select date '1970-01-02', time '00:00:01', timestamp '1970-01-01T08:00+08:00'")
+        {
+            Statement::Select(select) => {
+                let values: Vec<Expr> = select
+                    .items
+                    .into_iter()
+                    .map(|item| match item {
+                        SelectItem::Expr { expr, .. } => expr,
+                        SelectItem::Wildcard => unreachable!(),
+                    })
+                    .collect();
+                assert_eq!(
+                    values,
+                    vec![
+                        Expr::Literal(Value::Date(1)),
+                        Expr::Literal(Value::Time(1_000_000)),
+                        Expr::Literal(Value::DateTime(0)),
+                    ]
+                );
+            }
+            statement => panic!("not a select statement: {:?}", statement),
+        }
+        assert_eq!(
+            parse_err("select date '2024-02-30'").expected,
+            "date literal"
+        );
+        assert_eq!(parse_err("select time '25:00'").expected, "time literal");
+        assert_eq!(parse_err("create table e (d datetime(3))").expected, "`)`");
+    }
+
+    #[test]
+    fn integer() {
+        match parse("create table n (a tinyint, b smallint unsigned, c integer, d bigint unsigned not null)") {
+            Statement::CreateTable(create) => {
+                let types: Vec<(&str, usize, bool, bool)> = create
+                    .fields
+                    .iter()
+                    .map(|f| (f.fieldtype.as_str(), f.bitsize, f.unsigned, f.can_null))
+                    .collect();
+                assert_eq!(
+                    types,
+                    vec![
+                        ("tinyint", 1, false, true),
+                        ("smallint", 2, true, true),
+                        ("int", 4, false, true),
+                        ("bigint", 8, true, false),
+                    ]
+                );
+            }
+            statement => panic!("not a create table statement: {:?}", statement),
+        }
+        // 只有整数类型可以是 unsigned
+        assert_eq!(
+            parse_err("create table n (a char(3) unsigned)").expected,
+            "`)`"
+        );
+    }
+
+    #[test]
+    fn decimal() {
+        match parse("create table p (a decimal, b decimal(5), c decimal(38, 10))") {
+            Statement::CreateTable(create) => {
+                let sizes: Vec<(usize, usize)> = create
+                    .fields
+                    .iter()
+                    .map(|field| (field.bitsize, field.scale))
+                    .collect();
+                assert_eq!(sizes, vec![(10, 0), (5, 0), (38, 10)]);
+            }
+            statement => panic!("not a create table statement: {:?}", statement),
+        }
+        assert_eq!(
+            parse("select decimal '-12.50'"),
+            Statement::Select(Select {
+                items: vec![SelectItem::Expr {
+                    expr: Expr::Literal(Value::Decimal(Decimal::new(-1250, 2))),
+                    alias: None,
+                }],
+                from: None,
+                joins: vec![],
+                selection: None,
+                group_by: vec![],
+                having: None,
+                order_by: vec![],
+                limit: None,
+                offset: None,
+            })
+        );
+        assert_eq!(
+            parse_err("create table p (a decimal(39))").expected,
+            "precision between 1 and 38"
+        );
+        assert_eq!(
+            parse_err("create table p (a decimal(5, 6))").expected,
+            "scale between 0 and 5"
+        );
+        assert_eq!(
+            parse_err("select decimal '1e3'").expected,
+            "decimal literal"
+        );
+    }
+
+    #[test]
+    fn float() {
+        match parse("create table f (a float, b double not null, c boolean, d bool)") {
+            Statement::CreateTable(create) => {
+                let types: Vec<(&str, usize)> = create
+                    .fields
+                    .iter()
+                    .map(|field| (field.fieldtype.as_str(), field.bitsize))
+                    .collect();
+                assert_eq!(
+                    types,
+                    vec![("float", 4), ("double", 8), ("boolean", 1), ("boolean", 1)]
+                );
+            }
+            statement => panic!("not a create table statement: {:?}", statement),
+        }
+        assert_eq!(
+            parse("select * from f where a > .5 and b < -1.5e3"),
+            parse("select * from f where a > 0.5 and b < -15e2")
+        );
+        // 超出 f64 范围的字面量
+        let err = parse_err("select 1e999");
+        assert_eq!(err.expected, "expression");
+        assert_eq!(err.found, Some("1e999".to_string()));
+    }
+
+    #[test]
+    fn text_blob() {
+        match parse("create table b (body text not null, data blob)") {
+            Statement::CreateTable(create) => {
+                let types: Vec<(&str, usize, bool)> = create
+                    .fields
+                    .iter()
+                    .map(|f| (f.fieldtype.as_str(), f.bitsize, f.can_null))
+                    .collect();
+                assert_eq!(types, vec![("text", 16, false), ("blob", 16, true)]);
+            }
+            statement => panic!("not a create table statement: {:?}", statement),
+        }
+    }
+
+    #[test]
+    fn drop_alter() {
+        assert_eq!(
+            parse("drop table if exists user"),
+            Statement::DropTable(DropTable {
+                table: "user".to_string(),
+                if_exists: true,
+            })
+        );
+        assert_eq!(
+            parse("truncate table user"),
+            Statement::Truncate("user".to_string())
+        );
+        let alter = |operation| {
+            Statement::AlterTable(AlterTable {
+                table: "user".to_string(),
+                operation,
+            })
+        };
+        assert_eq!(
+            parse("alter table user add column age int not null"),
+            alter(AlterOperation::AddColumn(Field {
+                value: "age".to_string(),
+                fieldtype: "int".to_string(),
+                bitsize: 4,
+                can_null: false,
+                scale: 0,
+                unsigned: false,
+            }))
+        );
+        assert_eq!(
+            parse("alter table user drop age"),
+            alter(AlterOperation::DropColumn("age".to_string()))
+        );
+        assert_eq!(
+            parse("alter table user rename column name to nick"),
+            alter(AlterOperation::RenameColumn {
+                from: "name".to_string(),
+                to: "nick".to_string(),
+            })
+        );
+        assert_eq!(
+            parse("alter table user rename to people"),
+            alter(AlterOperation::RenameTable("people".to_string()))
+        );
+        assert_eq!(
+            parse_err("alter table user modify age int").expected,
+            "`add`, `drop` or `rename`"
+        );
+    }
+
+    #[test]
+    fn database() {
+        assert_eq!(
+            parse("create database if not exists shop"),
+            Statement::CreateDatabase {
+                name: "shop".to_string(),
+                if_not_exists: true,
+            }
+        );
+        assert_eq!(
+            parse("drop database shop"),
+            Statement::DropDatabase {
+                name: "shop".to_string(),
+                if_exists: false,
+            }
+        );
+        assert_eq!(parse("use `Shop`;"), Statement::Use("Shop".to_string()));
+        assert_eq!(parse_err("use").expected, "identifier");
+    }
+
+    #[test]
+    fn update_delete() {
+        let statement =
+            parse("update  user  set          id=1,name=\"acbeix\" where xxx=\"debsxnk\"");
+        let expected = Update {
+            table: "user".to_string(),
+            assignments: vec![
+                ("id".to_string(), Expr::Literal(Value::Int(1))),
+                (
+                    "name".to_string(),
+                    Expr::Literal(Value::Str("acbeix".to_string())),
+                ),
+            ],
+            selection: Some(Expr::binary(
+                Expr::column("xxx"),
+                BinaryOp::Eq,
+                Expr::Literal(Value::Str("debsxnk".to_string())),
+            )),
+        };
+        assert_eq!(statement, Statement::Update(expected));
+
+        let statement = parse("delete from user where id = 12");
+        let expected = Delete {
+            table: "user".to_string(),
+            selection: Some(Expr::binary(
+                Expr::column("id"),
+                BinaryOp::Eq,
+                Expr::Literal(Value::Int(12)),
+            )),
+        };
+        assert_eq!(statement, Statement::Delete(expected));
+    }
+
+    #[test]
+    fn precedence() {
+        let statement = parse("select * from t where not a = 1 or b >= 2 and (c <> 3 or d < 4)");
+        let selection = match statement {
+            Statement::Select(select) => select.selection.unwrap(),
+            _ => unreachable!(),
+        };
+        let expected = Expr::binary(
+            Expr::negate(Expr::binary(
+                Expr::column("a"),
+                BinaryOp::Eq,
+                Expr::Literal(Value::Int(1)),
+            )),
+            BinaryOp::Or,
+            Expr::binary(
+                Expr::binary(
+                    Expr::column("b"),
+                    BinaryOp::GtEq,
+                    Expr::Literal(Value::Int(2)),
+                ),
+                BinaryOp::And,
+                Expr::binary(
+                    Expr::binary(
+                        Expr::column("c"),
+                        BinaryOp::NotEq,
+                        Expr::Literal(Value::Int(3)),
+                    ),
+                    BinaryOp::Or,
+                    Expr::binary(
+                        Expr::column("d"),
+                        BinaryOp::Lt,
+                        Expr::Literal(Value::Int(4)),
+                    ),
+                ),
+            ),
+        );
+        assert_eq!(selection, expected);
+    }
+
+    #[test]
+    fn order_by_limit() {
+        let statement = parse("select * from t order by a, b desc, c asc limit 10 offset 20");
+        let select = match statement {
+            Statement::Select(select) => select,
+            _ => unreachable!(),
+        };
+        let order_by: Vec<(Expr, bool)> = select
+            .order_by
+            .into_iter()
+            .map(|item| (item.expr, item.desc))
+            .collect();
+        assert_eq!(
+            order_by,
+            vec![
+                (Expr::column("a"), false),
+                (Expr::column("b"), true),
+                (Expr::column("c"), false),
+            ]
+        );
+        assert_eq!((select.limit, select.offset), (Some(10), Some(20)));
+        let err = parse_err("select * from t limit -1");
+        assert_eq!(err.expected, "non-negative integer");
+        let err = parse_err("select * from t order a");
+        assert_eq!(err.expected, "`by`");
+    }
+
+    #[test]
+    fn aggregate() {
+        let statement = parse(
+            "select dept, COUNT(*), max(age) as oldest from emp group by dept having count(name) > 1",
+        );
+        let select = match statement {
+            Statement::Select(select) => select,
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            select.items[1],
+            SelectItem::Expr {
+                expr: Expr::Aggregate {
+                    func: AggregateFunc::Count,
+                    arg: None,
+                },
+                alias: None,
+            }
+        );
+        assert_eq!(select.group_by, vec![Expr::column("dept")]);
+        assert_eq!(
+            select.having,
+            Some(Expr::binary(
+                Expr::Aggregate {
+                    func: AggregateFunc::Count,
+                    arg: Some(Box::new(Expr::column("name"))),
+                },
+                BinaryOp::Gt,
+                Expr::Literal(Value::Int(1)),
+            ))
+        );
+        let err = parse_err("select upper(name) from emp");
+        assert_eq!(err.found, Some("upper".to_string()));
+        let err = parse_err("select sum(*) from emp");
+        assert_eq!(err.expected, "expression");
+    }
+
+    #[test]
+    fn join() {
+        let statement = parse(
+            "select u.name, o.id from user u join orders as o on u.id = o.user_id \
+             left outer join item on item.id = o.item_id where o.id > 1",
+        );
+        let select = match statement {
+            Statement::Select(select) => select,
+            _ => unreachable!(),
+        };
+        assert_eq!(select.joins.len(), 2);
+        assert_eq!(select.joins[0].kind, JoinKind::Inner);
+        assert_eq!(select.joins[0].table.qualifier(), "o");
+        assert_eq!(
+            select.joins[0].on,
+            Expr::binary(
+                Expr::qualified("u", "id"),
+                BinaryOp::Eq,
+                Expr::qualified("o", "user_id"),
+            )
+        );
+        assert_eq!(select.joins[1].kind, JoinKind::Left);
+        assert_eq!(select.joins[1].table.qualifier(), "item");
+        let err = parse_err("select * from a left b on a.id = b.id");
+        assert_eq!(err.expected, "`join`");
+        let err = parse_err("select * from a join b where a.id = b.id");
+        assert_eq!(err.expected, "`on`");
+    }
+
+    #[test]
+    fn subquery() {
+        let statement = parse(
+            "select name, (select count(*) from orders o where o.user_id = u.id) from users u \
+             where id not in (select user_id from banned) and exists (select 1 from orders)",
+        );
+        let select = match statement {
+            Statement::Select(select) => select,
+            _ => unreachable!(),
+        };
+        assert!(matches!(
+            select.items[1],
+            SelectItem::Expr {
+                expr: Expr::Subquery(_),
+                ..
+            }
+        ));
+        match select.selection.unwrap() {
+            Expr::Binary { left, right, .. } => {
+                assert!(matches!(*left, Expr::InSubquery { negated: true, .. }));
+                assert!(matches!(*right, Expr::Exists(_)));
+            }
+            expr => panic!("unexpected {:?}", expr),
+        }
+    }
+
+    #[test]
+    fn predicate() {
+        let selection = |sql: &str| match parse(sql) {
+            Statement::Select(select) => select.selection.unwrap(),
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            selection("select * from t where a between 1 and 5 and b not in (1, 'x')"),
+            Expr::binary(
+                Expr::Between {
+                    expr: Box::new(Expr::column("a")),
+                    low: Box::new(Expr::Literal(Value::Int(1))),
+                    high: Box::new(Expr::Literal(Value::Int(5))),
+                    negated: false,
+                },
+                BinaryOp::And,
+                Expr::InList {
+                    expr: Box::new(Expr::column("b")),
+                    list: vec![
+                        Expr::Literal(Value::Int(1)),
+                        Expr::Literal(Value::Str("x".to_string())),
+                    ],
+                    negated: true,
+                },
+            )
+        );
+        assert_eq!(
+            selection("select * from t where name not like 'a!%%' escape '!'"),
+            Expr::Like {
+                expr: Box::new(Expr::column("name")),
+                pattern: Box::new(Expr::Literal(Value::Str("a!%%".to_string()))),
+                escape: Some(Box::new(Expr::Literal(Value::Str("!".to_string())))),
+                negated: true,
+            }
+        );
+        let err = parse_err("select * from t where a between 1 or 2");
+        assert_eq!(err.expected, "`and`");
+        let err = parse_err("select * from t where a in ()");
+        assert_eq!(err.expected, "expression");
+    }
+
+    #[test]
+    fn script() {
+        let sql = "create table t (id int);\n;insert into t values (1);\nselect * from t";
+        let statements = Parser::new(token::tokenize(sql)).parse_script().unwrap();
+        assert_eq!(statements.len(), 3);
+        assert!(matches!(statements[1], Statement::Insert(_)));
+        assert_eq!(
+            Parser::new(token::tokenize(" ; ")).parse_script(),
+            Ok(vec![])
+        );
+        let err = Parser::new(token::tokenize("select a from t select b from t"))
+            .parse_script()
+            .unwrap_err();
+        assert_eq!((err.expected.as_str(), err.offset), ("`;`", 16));
+    }
+
+    #[test]
+    fn syntax_error() {
+        let sql = "insert into user(id) value (1)";
+        let err = parse_err(sql);
+        assert_eq!(err.expected, "`values` or `select`");
+        assert_eq!(err.found, Some("value".to_string()));
+        assert_eq!((err.offset, err.length), (21, 5));
+        assert_eq!(err.position(sql), (1, 22));
+
+        let err = parse_err("");
+        assert_eq!((err.found, err.offset), (None, 0));
+        let err = parse_err("select * from user where");
+        assert_eq!(err.to_string(), "expected expression, found end of input");
+        assert_eq!(err.offset, 24);
+        let err = parse_err("create table t (name varchar(99999999999999999999))");
+        assert_eq!(err.expected, "length between 1 and 255");
+        let err = parse_err("select * from t where name = 'abc");
+        assert_eq!(err.found, Some("'abc".to_string()));
+        let err = parse_err("select a from t; select b from t");
+        assert_eq!(err.expected, "end of statement");
     }
 }
@@ -1,115 +1,7 @@
-use std::collections::HashMap;
-use std::{fmt::Result, iter};
-
 use lazy_static::lazy_static;
-use regex::Regex;
 
-#[derive(Debug, Clone)]
-pub enum TokenState {
-    START,    // 需要判断后方数据
-    ANNO_PRE, // /
-    ANNO_A,   // //
-    ANNO_B,   // /*
-    ANNO_B2,  // /* * / *
-    READY,    // 注释结束了，且后面不是注释
-    BLANK,
-}
-
-pub fn remove_annotation(code: &str) -> String {
-    let mut state: TokenState = TokenState::START;
-    let mut old_state: TokenState = TokenState::START;
-    let mut mark: usize = 0;
-    let mut chars: Vec<char> = code.chars().into_iter().collect();
-    let mut chars_: Vec<char> = chars.clone();
-
-    let res: String = chars
-        .iter()
-        .enumerate()
-        .filter_map(|(i, c)| {
-            match c {
-                '/' => match state {
-                    TokenState::START => {
-                        if let Some(&c_1) = chars_.get(i + 1) {
-                            if c_1 == '/' || c_1 == '*' {
-                                state = TokenState::ANNO_PRE; // 可能是/ 或注释
-                            } else {
-                                state = TokenState::READY;
-                            }
-                        }
-                    }
-                    TokenState::READY => {
-                        if let Some(&c_1) = chars_.get(i + 1) {
-                            if c_1 == '/' || c_1 == '*' {
-                                state = TokenState::ANNO_PRE; // 可能是/ 或注释
-                            }
-                        }
-                    }
-                    TokenState::ANNO_PRE => {
-                        state = TokenState::ANNO_A; // 第一种注释
-                    }
-                    TokenState::ANNO_B2 => state = TokenState::START,
-                    _ => {}
-                },
-                '\n' => match state {
-                    TokenState::ANNO_A => {
-                        state = TokenState::START;
-                    }
-                    _ => {
-                        state = TokenState::READY;
-                    }
-                },
-                '*' => match state {
-                    TokenState::ANNO_PRE => {
-                        state = TokenState::ANNO_B;
-                    }
-                    TokenState::ANNO_B => {
-                        state = TokenState::ANNO_B2;
-                    }
-                    _ => {
-                        state = TokenState::READY;
-                    }
-                },
-                ' ' => match state {
-                    TokenState::READY => {
-                        if let Some(&c_1) = chars_.get(i + 1) {
-                            if c_1 == ' ' {
-                                old_state = state.clone();
-                                state = TokenState::BLANK; // 可能是/ 或注释
-                            }
-                        }
-                    }
-                    TokenState::BLANK => {
-                        if let Some(&c_1) = chars_.get(i + 1) {
-                            if c_1 != ' ' {
-                                state = old_state.clone();
-                            }
-                        }
-                    }
-                    _ => {}
-                },
-                _ => match state {
-                    TokenState::ANNO_A => {}
-                    TokenState::ANNO_B => {}
-                    TokenState::ANNO_B2 => {}
-                    _ => {
-                        state = TokenState::READY;
-                    }
-                },
-            };
-            let tmp;
-            match state {
-                TokenState::READY => {
-                    tmp = Ok(c);
-                }
-                _ => {
-                    tmp = Err("");
-                }
-            }
-            tmp.ok()
-        })
-        .collect();
-    res.as_str().trim().to_string()
-}
+use crate::core::decimal::Decimal;
+use crate::core::value::Value;
 
 lazy_static! {
     static ref OPERATIONS: Vec<String> = "+-*/%=&|<>!"
@@ -117,11 +9,30 @@ lazy_static! {
         .into_iter()
         .map(|c| c.to_string())
         .collect();
-    static ref BOUNDARYS: Vec<String> = "(),;".chars().into_iter().map(|c| c.to_string()).collect();
-    static ref ELEMTYPE: Vec<String> = vec!["int", "char", "date", "varchar", "time"]
-        .iter()
-        .map(|x| x.to_string())
-        .collect();
+    static ref BOUNDARYS: Vec<String> = "(),;.".chars().map(|c| c.to_string()).collect();
+    static ref ELEMTYPE: Vec<String> = vec![
+        "tinyint",
+        "smallint",
+        "int",
+        "integer",
+        "bigint",
+        "char",
+        "date",
+        "varchar",
+        "time",
+        "datetime",
+        "timestamp",
+        "decimal",
+        "float",
+        "double",
+        "boolean",
+        "bool",
+        "text",
+        "blob",
+    ]
+    .iter()
+    .map(|x| x.to_string())
+    .collect();
     static ref KEYWORDS: Vec<String> = vec![
         "select",
         "insert",
@@ -149,54 +60,52 @@ lazy_static! {
         "key",
         "by",
         "character",
-        "decimal",
         "null",
         "not",
         "values",
-        "order"
+        "order",
+        "asc",
+        "desc",
+        "limit",
+        "offset",
+        "group",
+        "having",
+        "inner",
+        "left",
+        "outer",
+        "on",
+        "exists",
+        "escape",
+        "if",
+        "truncate",
+        "rename",
+        "add",
+        "column",
+        "to",
+        "unique",
+        "unsigned",
     ]
     .iter()
     .map(|x| x.to_string())
     .collect();
 }
-pub fn trim_code(code: &str) -> String {
-    // 去注释
-    let mut re = Regex::new("/\\*[\\s\\S]*\\*/").unwrap();
-    let code = Regex::replace_all(&re, &code, "");
-    re = Regex::new("//.*\n").unwrap();
-    let code = Regex::replace_all(&re, &code, "");
-    // 去空格
-    re = Regex::new("\\s+").unwrap();
-    let code = Regex::replace_all(&re, &code, " ");
-
-    // 格式化运算符和边界符
-    re = Regex::new("\\s?=\\s?").unwrap();
-    let code = Regex::replace_all(&re, &code, " = ");
-    re = Regex::new("\\s?\\(\\s?").unwrap();
-    let code = Regex::replace_all(&re, &code, " ( ");
-    re = Regex::new("\\s?\\)\\s?").unwrap();
-    let code = Regex::replace_all(&re, &code, " ) ");
-    re = Regex::new("\\s?,\\s?").unwrap();
-    let code = Regex::replace_all(&re, &code, " , ");
-    re = Regex::new("\\s?;\\s?").unwrap();
-    let code = Regex::replace_all(&re, &code, " ; ");
-
-    // 变小写
-    let code = code.to_ascii_lowercase();
-    let code = code.trim();
-    code.to_string()
-}
-
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum TokenType {
     KeyWord,
     String,
     Operation,
     Boundary,
-    Number,
+    Integer, // 有符号整数
+    Float,   // 小数和科学计数法
+    Hex,     // 十六进制的 blob 字面量 x'0A1B'
+    Null,
+    Bool,
     ELEMTYPE,
+    Identifier,  // 表名、字段名等标识符
+    Placeholder, // 预处理语句的参数 ? 或者 $1
+    Unknown,     // 无法识别的字符，或者没有闭合的引号
 }
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Token {
     pub tokentype: TokenType,
     pub value: String,
@@ -212,40 +121,481 @@ impl Token {
             length,
         }
     }
+
+    // 字面量对应的值，不是字面量时返回 None
+    // 超出 i64 范围的整数按 decimal 处理，decimal 也放不下时才按浮点数处理，
+    // 超出 f64 范围的数字不是合法的字面量
+    pub fn to_value(&self) -> Option<Value> {
+        match self.tokentype {
+            // 超出 i64 的整数按 decimal 精确保存，bigint unsigned 的值都能写出来
+            TokenType::Integer => match self.value.parse::<i64>() {
+                Ok(n) => Some(Value::Int(n)),
+                Err(_) => Decimal::parse(&self.value)
+                    .map(Value::Decimal)
+                    .or_else(|| Self::float(&self.value)),
+            },
+            // 没有指数的小数按 decimal 精确保存，超出 decimal 的范围时才按浮点数处理
+            TokenType::Float => Decimal::parse(&self.value)
+                .map(Value::Decimal)
+                .or_else(|| Self::float(&self.value)),
+            TokenType::Hex => Self::hex(&self.value),
+            TokenType::String => Some(Value::Str(self.value.clone())),
+            TokenType::Null => Some(Value::Null),
+            TokenType::Bool => Some(Value::Bool(self.value == "true")),
+            _ => None,
+        }
+    }
+
+    // 每两个十六进制数字是一个字节
+    fn hex(s: &str) -> Option<Value> {
+        if s.len() % 2 == 1 || !s.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let bytes = (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect();
+        Some(Value::Bytes(bytes))
+    }
+
+    fn float(s: &str) -> Option<Value> {
+        s.parse::<f64>()
+            .ok()
+            .filter(|n| n.is_finite())
+            .map(Value::Float)
+    }
 }
 
-pub fn trim_to_token_stream(code: &str) -> Vec<Token> {
-    let mut vec = Vec::new();
-    let words: Vec<&str> = code.split_whitespace().collect();
-    let mut str_offset = 0;
-    for (_, word) in words.iter().enumerate() {
-        let word = word.to_string();
-        let word_len = word.len();
-        if OPERATIONS.contains(&word) {
-            vec.push(Token::new(TokenType::Operation, word, str_offset, word_len))
-        } else if BOUNDARYS.contains(&word) {
-            vec.push(Token::new(TokenType::Boundary, word, str_offset, word_len))
-        } else if KEYWORDS.contains(&word) {
-            vec.push(Token::new(TokenType::KeyWord, word, str_offset, word_len))
-        } else if ELEMTYPE.contains(&word) {
-            vec.push(Token::new(TokenType::ELEMTYPE, word, str_offset, word_len))
-        } else {
-            vec.push(Token::new(TokenType::String, word, str_offset, word_len))
-        }
-        str_offset += word_len + 1;
-    }
-    vec
+// 逐字符扫描 sql，offset 和 length 都是相对原始 sql 的字节位置
+pub struct Lexer<'a> {
+    code: &'a str,
+    chars: Vec<(usize, char)>,
+    pos: usize,
 }
 
-#[test]
-fn test() {
-    let code = r#"
+impl<'a> Lexer<'a> {
+    pub fn new(code: &'a str) -> Self {
+        Lexer {
+            code,
+            chars: code.char_indices().collect(),
+            pos: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).map(|&(_, c)| c)
+    }
+
+    fn peek_next(&self) -> Option<char> {
+        self.chars.get(self.pos + 1).map(|&(_, c)| c)
+    }
+
+    // 当前字符的字节位置，读完后就是 sql 的长度
+    fn offset(&self) -> usize {
+        match self.chars.get(self.pos) {
+            Some(&(offset, _)) => offset,
+            None => self.code.len(),
+        }
+    }
+
+    pub fn tokenize(mut self) -> Vec<Token> {
+        let mut vec = Vec::new();
+        loop {
+            self.skip_blank();
+            let c = match self.peek() {
+                Some(c) => c,
+                None => break,
+            };
+            let start = self.offset();
+            let (tokentype, value) = match c {
+                '\'' | '"' => self.read_quoted(c, TokenType::String),
+                '`' => self.read_quoted(c, TokenType::Identifier),
+                'x' | 'X' if self.peek_next() == Some('\'') => {
+                    self.pos += 1;
+                    self.read_quoted('\'', TokenType::Hex)
+                }
+                c if c.is_ascii_digit() => self.read_number(),
+                '.' if Self::expect_value(&vec) && self.next_is_digit() => self.read_number(),
+                '-' | '+' if Self::expect_value(&vec) && self.is_number_at(self.pos + 1) => {
+                    self.pos += 1;
+                    let (tokentype, number) = self.read_number();
+                    (tokentype, format!("{}{}", c, number))
+                }
+                c if c.is_alphabetic() || c == '_' => {
+                    // 关键字和类型名不区分大小写，没有引号的标识符统一折叠为小写，
+                    // 所以 User 和 user 是同一张表；反引号里的标识符和字符串保持原样
+                    let word = self.read_word().to_ascii_lowercase();
+                    if word == "null" {
+                        (TokenType::Null, word)
+                    } else if word == "true" || word == "false" {
+                        (TokenType::Bool, word)
+                    } else if KEYWORDS.contains(&word) {
+                        (TokenType::KeyWord, word)
+                    } else if ELEMTYPE.contains(&word) {
+                        (TokenType::ELEMTYPE, word)
+                    } else {
+                        (TokenType::Identifier, word)
+                    }
+                }
+                '?' => {
+                    self.pos += 1;
+                    (TokenType::Placeholder, c.to_string())
+                }
+                '$' if self.next_is_digit() => {
+                    self.pos += 1;
+                    let start = self.offset();
+                    self.eat_digits();
+                    let number = &self.code[start..self.offset()];
+                    (TokenType::Placeholder, format!("${}", number))
+                }
+                c if BOUNDARYS.contains(&c.to_string()) => {
+                    self.pos += 1;
+                    (TokenType::Boundary, c.to_string())
+                }
+                c if OPERATIONS.contains(&c.to_string()) => {
+                    (TokenType::Operation, self.read_operation())
+                }
+                c => {
+                    self.pos += 1;
+                    (TokenType::Unknown, c.to_string())
+                }
+            };
+            vec.push(Token::new(tokentype, value, start, self.offset() - start));
+        }
+        vec
+    }
+
+    // 跳过空白和注释：/* */ 块注释，//、-- 和 # 行注释
+    // 只在 token 之间识别注释，引号中的内容由 read_quoted 原样读取
+    fn skip_blank(&mut self) {
+        loop {
+            match (self.peek(), self.peek_next()) {
+                (Some(c), _) if c.is_whitespace() => self.pos += 1,
+                (Some('/'), Some('*')) => {
+                    self.pos += 2;
+                    while let Some(c) = self.peek() {
+                        if c == '*' && self.peek_next() == Some('/') {
+                            self.pos += 2;
+                            break;
+                        }
+                        self.pos += 1;
+                    }
+                }
+                (Some('/'), Some('/')) | (Some('-'), Some('-')) | (Some('#'), _) => {
+                    while let Some(c) = self.peek() {
+                        if c == '\n' {
+                            break;
+                        }
+                        self.pos += 1;
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    // 读取引号包裹的内容，支持 \ 转义和连续两个引号的写法
+    // 没有闭合时整段作为 Unknown 返回
+    fn read_quoted(&mut self, quote: char, tokentype: TokenType) -> (TokenType, String) {
+        let start = self.offset();
+        self.pos += 1;
+        let mut s = String::new();
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            if c == quote {
+                if self.peek() == Some(quote) {
+                    s.push(quote);
+                    self.pos += 1;
+                    continue;
+                }
+                return (tokentype, s);
+            }
+            if c == '\\' && quote != '`' {
+                match self.peek() {
+                    Some(e) => {
+                        self.pos += 1;
+                        s.push(match e {
+                            'n' => '\n',
+                            't' => '\t',
+                            'r' => '\r',
+                            '0' => '\0',
+                            e => e,
+                        });
+                    }
+                    None => break,
+                }
+                continue;
+            }
+            s.push(c);
+        }
+        (TokenType::Unknown, self.code[start..].to_string())
+    }
+
+    fn read_word(&mut self) -> String {
+        let start = self.offset();
+        while let Some(c) = self.peek() {
+            if c.is_alphanumeric() || c == '_' {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        self.code[start..self.offset()].to_string()
+    }
+
+    // 前一个 token 之后应该是一个值时，- 和 + 才是数字的符号，否则是运算符
+    fn expect_value(vec: &[Token]) -> bool {
+        match vec.last() {
+            None => true,
+            Some(token) => match token.tokentype {
+                TokenType::Operation | TokenType::KeyWord => true,
+                TokenType::Boundary => token.value != ")",
+                _ => false,
+            },
+        }
+    }
+
+    fn next_is_digit(&self) -> bool {
+        self.peek_next().is_some_and(|c| c.is_ascii_digit())
+    }
+
+    // 数字以数字或者 .数字 开头
+    fn is_number_at(&self, pos: usize) -> bool {
+        let at = |pos| self.chars.get(pos).map(|&(_, c)| c);
+        match at(pos) {
+            Some(c) if c.is_ascii_digit() => true,
+            Some('.') => at(pos + 1).is_some_and(|c| c.is_ascii_digit()),
+            _ => false,
+        }
+    }
+
+    // 整数、小数和科学计数法，小数点前后的数字可以省略一边：.5、5.
+    fn read_number(&mut self) -> (TokenType, String) {
+        let start = self.offset();
+        let mut tokentype = TokenType::Integer;
+        self.eat_digits();
+        if self.peek() == Some('.') {
+            tokentype = TokenType::Float;
+            self.pos += 1;
+            self.eat_digits();
+        }
+        if let Some('e') | Some('E') = self.peek() {
+            let digit_at = match self.peek_next() {
+                Some('+') | Some('-') => self.pos + 2,
+                _ => self.pos + 1,
+            };
+            if let Some(&(_, c)) = self.chars.get(digit_at) {
+                if c.is_ascii_digit() {
+                    tokentype = TokenType::Float;
+                    self.pos = digit_at;
+                    self.eat_digits();
+                }
+            }
+        }
+        (tokentype, self.code[start..self.offset()].to_string())
+    }
+
+    fn eat_digits(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_ascii_digit() {
+                break;
+            }
+            self.pos += 1;
+        }
+    }
+
+    // <= >= <> != 作为一个运算符，其余都是单字符
+    fn read_operation(&mut self) -> String {
+        let c = self.peek().unwrap();
+        self.pos += 1;
+        match (c, self.peek()) {
+            ('<', Some('=')) | ('>', Some('=')) | ('<', Some('>')) | ('!', Some('=')) => {
+                let next = self.peek().unwrap();
+                self.pos += 1;
+                format!("{}{}", c, next)
+            }
+            _ => c.to_string(),
+        }
+    }
+}
+
+pub fn tokenize(code: &str) -> Vec<Token> {
+    Lexer::new(code).tokenize()
+}
+
+// 关键字、类型名和 null、true、false 不能直接用作标识符，需要用反引号包裹
+pub fn is_reserved(word: &str) -> bool {
+    let word = word.to_ascii_lowercase();
+    KEYWORDS.contains(&word)
+        || ELEMTYPE.contains(&word)
+        || ["null", "true", "false"].contains(&word.as_str())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn values(code: &str) -> Vec<String> {
+        tokenize(code).into_iter().map(|t| t.value).collect()
+    }
+
+    #[test]
+    fn test() {
+        let code = r#"
         SELECT  * from  adwdw where   a   =ad  and b=ad and ;
         insert into user(id,name)values(1,"saadwdd")where id=1;
     "#;
-    let code = trim_code(code);
-    println!("{:?}", code);
-    println!("{}", code);
-    let token_stream = trim_to_token_stream(&code);
-    println!("{:#?}", token_stream);
+        let token_stream = tokenize(code);
+        println!("{:#?}", token_stream);
+    }
+
+    #[test]
+    fn quoted_string() {
+        let tokens = tokenize(r#"insert into user(name,note)values("hello, world",'a=b')"#);
+        let strings: Vec<&Token> = tokens
+            .iter()
+            .filter(|t| t.tokentype == TokenType::String)
+            .collect();
+        assert_eq!(strings[strings.len() - 2].value, "hello, world");
+        assert_eq!(strings[strings.len() - 1].value, "a=b");
+        assert_eq!(tokens.len(), 14);
+    }
+
+    #[test]
+    fn escape() {
+        assert_eq!(
+            values(r#"'it''s' 'a\'b' "x\ny" `my``col`"#),
+            vec!["it's", "a'b", "x\ny", "my`col"]
+        );
+        let tokens = tokenize("'abc");
+        assert_eq!(tokens[0].tokentype, TokenType::Unknown);
+    }
+
+    #[test]
+    fn operation() {
+        assert_eq!(
+            values("a>=1and b<>2 or c!=3.5"),
+            vec!["a", ">=", "1", "and", "b", "<>", "2", "or", "c", "!=", "3.5"]
+        );
+        assert_eq!(values("id=1;"), vec!["id", "=", "1", ";"]);
+    }
+
+    #[test]
+    fn offset() {
+        let code = "select  name from `user`\nwhere id = 'x y'";
+        for token in tokenize(code) {
+            let text = &code[token.offset..token.offset + token.length];
+            println!("{:?} {:?}", token.value, text);
+        }
+        let tokens = tokenize(code);
+        assert_eq!((tokens[1].offset, tokens[1].length), (8, 4));
+        assert_eq!((tokens[3].offset, tokens[3].length), (18, 6));
+        assert_eq!((tokens[7].offset, tokens[7].length), (36, 5));
+    }
+
+    #[test]
+    fn case() {
+        let tokens = tokenize("INSERT Into `MyTable`(Name) VALUES ('Alice', \"BoB\")");
+        assert_eq!(tokens[0].tokentype, TokenType::KeyWord);
+        assert_eq!(tokens[0].value, "insert");
+        assert_eq!(tokens[1].value, "into");
+        assert_eq!(tokens[2].tokentype, TokenType::Identifier);
+        assert_eq!(tokens[2].value, "MyTable");
+        assert_eq!(tokens[4].tokentype, TokenType::Identifier);
+        assert_eq!(tokens[4].value, "name");
+        assert_eq!(tokens[8].value, "Alice");
+        assert_eq!(tokens[10].value, "BoB");
+        assert_eq!(values("create table T (c VARCHAR(5))")[2], "t");
+        assert_eq!(tokenize("VarChar")[0].tokentype, TokenType::ELEMTYPE);
+    }
+
+    #[test]
+    fn literal() {
+        let tokens = tokenize("values(-5, 3.25, 1e3, 'x', NULL, true, False, a-1, b - -2)");
+        let types: Vec<TokenType> = tokens.iter().map(|t| t.tokentype.clone()).collect();
+        assert_eq!(types[2], TokenType::Integer);
+        assert_eq!(tokens[2].value, "-5");
+        assert_eq!(types[4], TokenType::Float);
+        assert_eq!(types[6], TokenType::Float);
+        assert_eq!(types[10], TokenType::Null);
+        assert_eq!(types[12], TokenType::Bool);
+        assert_eq!(types[14], TokenType::Bool);
+        // a-1 中的 - 是运算符
+        assert_eq!(values("a-1"), vec!["a", "-", "1"]);
+        assert_eq!(values("b - -2"), vec!["b", "-", "-2"]);
+        // 小数点前后的数字可以省略一边，t.c 中的 . 仍然是分隔符
+        assert_eq!(
+            values("select .5, -.5, 5., 1.5e-3, 2E+2, t.c"),
+            vec![
+                "select", ".5", ",", "-.5", ",", "5.", ",", "1.5e-3", ",", "2E+2", ",", "t", ".",
+                "c"
+            ]
+        );
+        let float = |s: &str| tokenize(s)[0].to_value();
+        // 没有指数的小数是精确的 decimal，超过 17 位有效数字也不会丢失精度
+        assert_eq!(float("-.5"), Some(Value::Decimal(Decimal::new(-5, 1))));
+        assert_eq!(float("5."), Some(Value::Decimal(Decimal::new(5, 0))));
+        assert_eq!(
+            float("12345678901234567.89"),
+            Some(Value::Decimal(Decimal::new(1234567890123456789, 2)))
+        );
+        assert_eq!(float("1.5e-3"), Some(Value::Float(0.0015)));
+        assert_eq!(
+            float(&format!("0.{}1", "0".repeat(40))),
+            Some(Value::Float(1e-41))
+        );
+        assert_eq!(float("1e400"), None);
+        assert_eq!(float(&"9".repeat(400)), None);
+        // x'..' 是 blob，x 后面没有引号时是标识符
+        assert_eq!(
+            tokenize("X'00fF'")[0].to_value(),
+            Some(Value::Bytes(vec![0, 0xff]))
+        );
+        assert_eq!(tokenize("x''")[0].to_value(), Some(Value::Bytes(vec![])));
+        assert_eq!(tokenize("x'abc'")[0].to_value(), None);
+        assert_eq!(values("x = 1"), vec!["x", "=", "1"]);
+
+        let values: Vec<Value> = tokens.iter().filter_map(|t| t.to_value()).collect();
+        assert_eq!(
+            values[..7],
+            [
+                Value::Int(-5),
+                Value::Decimal(Decimal::new(325, 2)),
+                Value::Float(1000.0),
+                Value::Str("x".to_string()),
+                Value::Null,
+                Value::Bool(true),
+                Value::Bool(false),
+            ]
+        );
+    }
+
+    #[test]
+    fn annotation() {
+        assert_eq!(
+            values("select /* a, b */ c // d\n from t"),
+            vec!["select", "c", "from", "t"]
+        );
+        assert_eq!(
+            values("select a -- b, c\n# d\nfrom t # e\nwhere a = 1 --"),
+            vec!["select", "a", "from", "t", "where", "a", "=", "1"]
+        );
+        // 没有闭合的块注释一直到末尾
+        assert_eq!(values("select 1 /* a"), vec!["select", "1"]);
+        // 引号中像注释的内容保持原样
+        assert_eq!(
+            values("values ('http://a.com/x--y#z', \"/* b */\", `c--d`)"),
+            vec![
+                "values",
+                "(",
+                "http://a.com/x--y#z",
+                ",",
+                "/* b */",
+                ",",
+                "c--d",
+                ")"
+            ]
+        );
+        assert_eq!(values("a - -2"), vec!["a", "-", "-2"]);
+    }
 }